@@ -1,17 +1,82 @@
-use std::fs;
+#[path = "../common/flags.rs"]
+mod flags;
+#[path = "../common/rng.rs"]
+mod rng;
+#[path = "../common/table.rs"]
+mod table;
+
+use flags::{flag_number, flag_value};
+use rng::Rng;
+use std::{env, fs};
+use table::{Align, print_table};
 
 const INPUT_PATH: &str = "inputs/1.txt";
 const START_POSITION: i32 = 50;
 const DIAL_SIZE: i32 = 100;
+const TARGET_MARK: i32 = 0;
 
 /// https://adventofcode.com/2025/day/1
+///
+/// The dial can be changed with `--size <marks>`, `--start <mark>` and `--target <mark>`, for the
+/// number of marks around it, the mark it starts on and the mark to count.
+///
+/// `--trace <table|csv>` prints every turn with where the dial went and how often it passed the
+/// target, adding `--crossings-only` leaves out the turns that never passed it.
+///
+/// `--check <cases>` compares the counting against clicking the dial round one mark at a time, for
+/// random dials and turns seeded with `--seed <seed>`.
+///
+/// The turns can be read from another file with `--input <path>`. If it declares several dials
+/// in a header, see [`parse_input`], it is treated as a combination lock instead.
 fn main() {
-    let input = match parse_input(INPUT_PATH) {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match parse_check(&args) {
+        Ok(Some((cases, seed))) => return print_check(cases, run_check(cases, seed)),
+        Ok(None) => {}
+        Err(e) => return println!("Failed with error: {}", e),
+    }
+    let dial = match parse_dial(&args) {
+        Ok(dial) => dial,
+        Err(e) => return println!("Failed with error: {}", e),
+    };
+    let trace_format = match flag_value(&args, "--trace")
+        .and_then(|format| format.map(TraceFormat::parse).transpose())
+    {
+        Ok(trace_format) => trace_format,
+        Err(e) => return println!("Failed with error: {}", e),
+    };
+    let input = match flag_value(&args, "--input")
+        .and_then(|input_path| parse_input(input_path.unwrap_or(INPUT_PATH)))
+    {
         Ok(input) => input,
         Err(e) => return println!("Failed with error: {}", e),
     };
-    println!("Answer 1 is: {}", calculate_times_landed_on_zero(&input));
-    println!("Answer 2 is: {}", calculate_times_passed_zero(&input));
+    if !input.dials.is_empty() {
+        if trace_format.is_some() {
+            return println!("Failed with error: --trace only works with a single dial");
+        }
+        return match open_lock(&input, dial.target) {
+            Ok(lock) => lock.print(dial.target),
+            Err(e) => println!("Failed with error: {}", e),
+        };
+    }
+    let input: Vec<i32> = input.turns.iter().map(|&(_, degrees)| degrees).collect();
+    if let Some(format) = trace_format {
+        let crossings_only = args.iter().any(|arg| arg == "--crossings-only");
+        let records: Vec<_> = dial
+            .trace(&input)
+            .into_iter()
+            .filter(|record| !crossings_only || record.crossings > 0)
+            .collect();
+        print_trace(&records, format);
+        // Keep CSV output clean so that it can be saved straight to a file.
+        if format == TraceFormat::Csv {
+            return;
+        }
+    }
+    let counts = dial.count_target(&input);
+    println!("Answer 1 is: {}", counts.landed);
+    println!("Answer 2 is: {}", counts.passed);
 }
 
 /// Input is a list of directions to turn in the format [LR](\d+)
@@ -22,56 +87,523 @@ fn main() {
 ///
 /// We parse this and return a vector of integers, with positive values being a turn to the right (clockwise)
 /// and negative values to the left.
-fn parse_input(input_path: &str) -> Result<Vec<i32>, String> {
+///
+/// For a lock with several dials the input starts with a header line for each dial giving its name,
+/// size and start position, and every turn names the dial it turns
+/// e.g.
+/// dial A size=100 start=50
+/// dial B size=10 start=0
+/// A:L23
+/// B:R5
+fn parse_input(input_path: &str) -> Result<Instructions, String> {
     let input_text = fs::read_to_string(input_path).map_err(|e| e.to_string())?;
-    input_text
-        .lines()
-        .map(|l| {
-            let (dir, degrees_str) = l.split_at(1);
-            let degrees = degrees_str.parse::<i32>().map_err(|e| e.to_string())?;
-            match dir {
-                "L" => Ok(-degrees),
-                "R" => Ok(degrees),
-                s => Err(format!("Unexpected start of line: {}", s)),
+    let mut instructions = Instructions {
+        dials: vec![],
+        turns: vec![],
+    };
+    for (i, l) in input_text.lines().enumerate() {
+        let line_error = |e: String| format!("line {}: {}", i + 1, e);
+        if let Some(declaration) = l.strip_prefix("dial ") {
+            if !instructions.turns.is_empty() {
+                return Err(line_error(
+                    "dials must be declared before any turns".to_owned(),
+                ));
             }
+            let declaration = parse_dial_declaration(declaration).map_err(line_error)?;
+            if instructions
+                .dials
+                .iter()
+                .any(|dial| dial.name == declaration.name)
+            {
+                return Err(line_error(format!(
+                    "dial {} is declared twice",
+                    declaration.name
+                )));
+            }
+            instructions.dials.push(declaration);
+            continue;
+        }
+        let (dial, turn) = match l.split_once(':') {
+            Some((name, turn)) => {
+                let dial = instructions
+                    .dials
+                    .iter()
+                    .position(|dial| dial.name == name)
+                    .ok_or_else(|| line_error(format!("unknown dial {}", name)))?;
+                (dial, turn)
+            }
+            None if instructions.dials.len() > 1 => {
+                return Err(line_error("turn doesn't say which dial to turn".to_owned()));
+            }
+            None => (0, l),
+        };
+        let Some(degrees_str) = turn.get(1..) else {
+            return Err(line_error("Empty turn".to_owned()));
+        };
+        let degrees = degrees_str
+            .parse::<i32>()
+            .map_err(|e| line_error(e.to_string()))?;
+        let degrees = match &turn[..1] {
+            "L" => -degrees,
+            "R" => degrees,
+            s => return Err(line_error(format!("Unexpected start of turn: {}", s))),
+        };
+        instructions.turns.push((dial, degrees));
+    }
+    Ok(instructions)
+}
+
+/// The turns read from the input, along with any dials it declares.
+struct Instructions {
+    /// The dials declared in the header, empty if there wasn't one.
+    dials: Vec<DialDeclaration>,
+    /// Every turn, with the index of the dial it turns, which is always 0 without a header.
+    turns: Vec<(usize, i32)>,
+}
+
+struct DialDeclaration {
+    name: String,
+    size: i32,
+    start: i32,
+}
+
+/// A dial from a header line, such as `A size=100 start=50` once the leading `dial` is removed.
+fn parse_dial_declaration(declaration: &str) -> Result<DialDeclaration, String> {
+    let parts: Vec<_> = declaration.split_whitespace().collect();
+    let [name, size, start] = parts.as_slice() else {
+        return Err(format!(
+            "Expected dial <name> size=<size> start=<start>, got dial {}",
+            declaration
+        ));
+    };
+    let setting = |part: &str, key: &str| -> Result<i32, String> {
+        part.strip_prefix(key)
+            .and_then(|value| value.strip_prefix('='))
+            .ok_or(format!("Expected {}=<number>, got {}", key, part))?
+            .parse()
+            .map_err(|e| format!("Invalid {}: {}", key, e))
+    };
+    if name.contains(':') {
+        return Err(format!("Dial name {} can't contain ':'", name));
+    }
+    Ok(DialDeclaration {
+        name: name.to_string(),
+        size: setting(size, "size")?,
+        start: setting(start, "start")?,
+    })
+}
+
+/// The number of cases and seed for a check if one was asked for on the command line.
+fn parse_check(args: &[String]) -> Result<Option<(u32, u64)>, String> {
+    let Some(cases) = flag_number(args, "--check")? else {
+        return Ok(None);
+    };
+    let seed = flag_number(args, "--seed")?.unwrap_or(0);
+    Ok(Some((cases, seed)))
+}
+
+/// Build the dial from the command line arguments, using the puzzle's dial for anything not given.
+fn parse_dial(args: &[String]) -> Result<Dial, String> {
+    Dial::new(
+        flag_number(args, "--size")?.unwrap_or(DIAL_SIZE),
+        flag_number(args, "--start")?.unwrap_or(START_POSITION),
+        flag_number(args, "--target")?.unwrap_or(TARGET_MARK),
+    )
+}
+
+/// How a combination lock with several dials went.
+struct LockReport {
+    names: Vec<String>,
+    /// How many times each dial landed on and passed the target.
+    counts: Vec<TargetCounts>,
+    /// The instructions, counting from 1, after which every dial pointed at the target.
+    all_on_target: Vec<usize>,
+}
+
+/// Follow every turn of a lock declared in the input, where every dial counts `target`.
+fn open_lock(instructions: &Instructions, target: i32) -> Result<LockReport, String> {
+    let mut dials = instructions
+        .dials
+        .iter()
+        .map(|dial| {
+            Dial::new(dial.size, dial.start, target)
+                .map_err(|e| format!("dial {}: {}", dial.name, e))
         })
-        .collect()
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut counts = vec![TargetCounts::default(); dials.len()];
+    let mut all_on_target = vec![];
+    for (i, &(dial, degrees)) in instructions.turns.iter().enumerate() {
+        counts[dial].passed += dials[dial].turn(degrees);
+        if dials[dial].position == target {
+            counts[dial].landed += 1;
+        }
+        if dials.iter().all(|dial| dial.position == target) {
+            all_on_target.push(i + 1);
+        }
+    }
+    Ok(LockReport {
+        names: instructions
+            .dials
+            .iter()
+            .map(|dial| dial.name.clone())
+            .collect(),
+        counts,
+        all_on_target,
+    })
 }
 
-fn calculate_times_landed_on_zero(turns: &[i32]) -> u32 {
-    let mut position = START_POSITION;
-    let mut reached_zero = 0;
-    turns.iter().for_each(|degrees| {
-        position = i32::rem_euclid(position + degrees, DIAL_SIZE);
-        if position == 0 {
-            reached_zero += 1;
+impl LockReport {
+    fn print(&self, target: i32) {
+        for (name, counts) in self.names.iter().zip(&self.counts) {
+            println!(
+                "Dial {} landed on {} {} times and passed it {} times",
+                name, target, counts.landed, counts.passed
+            );
         }
-    });
-    reached_zero
-}
-
-fn calculate_times_passed_zero(turns: &[i32]) -> u32 {
-    let mut position = START_POSITION;
-    let mut passed_zero: u32 = 0;
-    turns.iter().for_each(|&degrees| {
-        let distance_to_zero = match degrees {
-            d if d < 0 => {
-                if position == 0 {
-                    100
-                } else {
-                    position
-                }
+        if self.all_on_target.is_empty() {
+            println!("The dials never all read {} at once", target);
+        } else {
+            let instructions: Vec<_> = self.all_on_target.iter().map(usize::to_string).collect();
+            println!(
+                "Every dial read {} after instructions {}",
+                target,
+                instructions.join(", ")
+            );
+        }
+    }
+}
+
+/// A dial with `size` marks numbered from 0, pointing at `position`.
+#[derive(Clone, Copy, Debug)]
+struct Dial {
+    size: i32,
+    position: i32,
+    /// The mark we count the dial landing on and passing.
+    target: i32,
+}
+
+/// How many times the dial landed on and passed its target mark.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct TargetCounts {
+    /// Times a turn finished on the target.
+    landed: u32,
+    /// Times the dial pointed at the target during a turn, including when finishing on it.
+    passed: u32,
+}
+
+impl Dial {
+    fn new(size: i32, start: i32, target: i32) -> Result<Self, String> {
+        if size <= 0 {
+            return Err(format!("Dial size must be positive, got {}", size));
+        }
+        for (name, mark) in [("Start", start), ("Target", target)] {
+            if !(0..size).contains(&mark) {
+                return Err(format!(
+                    "{} mark {} is not on a dial of size {}",
+                    name, mark, size
+                ));
             }
-            d if d > 0 => DIAL_SIZE - position,
-            _ => 100,
+        }
+        Ok(Self {
+            size,
+            position: start,
+            target,
+        })
+    }
+
+    /// Turn the dial by `degrees` marks, to the right if positive, returning the number of times
+    /// it passed the target along the way.
+    fn turn(&mut self, degrees: i32) -> u32 {
+        // How far we have to turn before first reaching the target, a full turn if we start on it.
+        let distance_to_target = match degrees {
+            d if d < 0 => i32::rem_euclid(self.position - self.target, self.size),
+            _ => i32::rem_euclid(self.target - self.position, self.size),
+        };
+        let distance_to_target = if distance_to_target == 0 {
+            self.size
+        } else {
+            distance_to_target
         };
         let positive_degrees = i32::abs(degrees);
-        if positive_degrees >= distance_to_zero {
-            passed_zero += 1;
-            let remaining_degrees = positive_degrees - distance_to_zero;
-            passed_zero += (remaining_degrees / DIAL_SIZE) as u32;
+        let mut passed = 0;
+        if degrees != 0 && positive_degrees >= distance_to_target {
+            let remaining_degrees = positive_degrees - distance_to_target;
+            passed = 1 + (remaining_degrees / self.size) as u32;
+        }
+        self.position = i32::rem_euclid(self.position + degrees % self.size, self.size);
+        passed
+    }
+
+    /// Follow every turn from the dial's current position, recording each one.
+    fn trace(mut self, turns: &[i32]) -> Vec<TraceRecord> {
+        turns
+            .iter()
+            .enumerate()
+            .map(|(i, &degrees)| {
+                let before = self.position;
+                let crossings = self.turn(degrees);
+                TraceRecord {
+                    line: i + 1,
+                    degrees,
+                    before,
+                    after: self.position,
+                    landed: self.position == self.target,
+                    crossings,
+                }
+            })
+            .collect()
+    }
+
+    /// Follow every turn from the dial's current position, counting landings on and passes of
+    /// the target together.
+    fn count_target(mut self, turns: &[i32]) -> TargetCounts {
+        let mut counts = TargetCounts::default();
+        for &degrees in turns {
+            counts.passed += self.turn(degrees);
+            if self.position == self.target {
+                counts.landed += 1;
+            }
+        }
+        counts
+    }
+
+    /// The same as [`Dial::count_target`], but slowly turning the dial one mark at a time, so
+    /// that it is obviously right.
+    fn count_target_by_clicks(mut self, turns: &[i32]) -> TargetCounts {
+        let mut counts = TargetCounts::default();
+        for &degrees in turns {
+            for _ in 0..degrees.abs() {
+                self.position = (self.position + degrees.signum()).rem_euclid(self.size);
+                if self.position == self.target {
+                    counts.passed += 1;
+                }
+            }
+            if self.position == self.target {
+                counts.landed += 1;
+            }
+        }
+        counts
+    }
+}
+
+/// A dial and turns that the counting gets wrong.
+#[derive(Clone, Debug)]
+struct Counterexample {
+    dial: Dial,
+    turns: Vec<i32>,
+}
+
+impl Counterexample {
+    fn fails(&self) -> bool {
+        self.dial.count_target(&self.turns) != self.dial.count_target_by_clicks(&self.turns)
+    }
+
+    /// Smaller versions of this case to try, each one step simpler.
+    fn simplifications(&self) -> Vec<Self> {
+        let mut simpler = vec![];
+        let Dial {
+            size,
+            position,
+            target,
+        } = self.dial;
+        for i in 0..self.turns.len() {
+            let mut turns = self.turns.clone();
+            turns.remove(i);
+            simpler.push(Self {
+                dial: self.dial,
+                turns,
+            });
+            let degrees = self.turns[i];
+            for smaller in [degrees / 2, degrees - degrees.signum()] {
+                if smaller != degrees {
+                    let mut turns = self.turns.clone();
+                    turns[i] = smaller;
+                    simpler.push(Self {
+                        dial: self.dial,
+                        turns,
+                    });
+                }
+            }
+        }
+        let mut dials = vec![];
+        if size > 1 {
+            dials.push((size - 1, position % (size - 1), target % (size - 1)));
+        }
+        if position > 0 {
+            dials.push((size, position - 1, target));
+        }
+        if target > 0 {
+            dials.push((size, position, target - 1));
+        }
+        for (size, start, target) in dials {
+            if let Ok(dial) = Dial::new(size, start, target) {
+                simpler.push(Self {
+                    dial,
+                    turns: self.turns.clone(),
+                });
+            }
+        }
+        simpler
+    }
+
+    /// Keep simplifying the case for as long as it still fails.
+    fn shrink(mut self) -> Self {
+        while let Some(simpler) = self
+            .simplifications()
+            .into_iter()
+            .find(|simpler| simpler.fails())
+        {
+            self = simpler;
         }
-        position = i32::rem_euclid(position + degrees, DIAL_SIZE);
+        self
+    }
+}
+
+/// Compare the counting against clicking through for `cases` random dials, returning the smallest
+/// failing case we can find if there is one.
+fn run_check(cases: u32, seed: u64) -> Option<Counterexample> {
+    let mut rng = Rng::new(seed);
+    // None of the ranges here are ever empty.
+    let mut below = |bound: u64| rng.below(bound).expect("range isn't empty");
+    for _ in 0..cases {
+        let size = 1 + below(20) as i32;
+        let Ok(dial) = Dial::new(size, below(size as u64) as i32, below(size as u64) as i32) else {
+            continue;
+        };
+        // Favour the awkward turns: none at all, and whole numbers of revolutions.
+        let turns = (0..below(8))
+            .map(|_| {
+                let degrees = match below(4) {
+                    0 => 0,
+                    1 => size * below(4) as i32,
+                    _ => below(4 * size as u64) as i32,
+                };
+                if below(2) == 0 { -degrees } else { degrees }
+            })
+            .collect();
+        let example = Counterexample { dial, turns };
+        if example.fails() {
+            return Some(example.shrink());
+        }
+    }
+    None
+}
+
+/// Report the outcome of [`run_check`].
+fn print_check(cases: u32, counterexample: Option<Counterexample>) {
+    match counterexample {
+        Some(example) => {
+            let turns: Vec<_> = example.turns.iter().map(|&d| rotation(d)).collect();
+            println!(
+                "Found a failing case, shrunk to a dial of size {} starting at {} with target {} and turns [{}]",
+                example.dial.size,
+                example.dial.position,
+                example.dial.target,
+                turns.join(", ")
+            );
+            println!(
+                "Counted {:?} but clicking through gives {:?}",
+                example.dial.count_target(&example.turns),
+                example.dial.count_target_by_clicks(&example.turns)
+            );
+        }
+        None => println!(
+            "Checked {} random dials against clicking through every turn, no differences found",
+            cases
+        ),
+    }
+}
+
+/// A turn written as it is in the input.
+fn rotation(degrees: i32) -> String {
+    match degrees {
+        d if d < 0 => format!("L{}", -d),
+        d => format!("R{}", d),
+    }
+}
+
+/// What happened to the dial during a single turn.
+#[derive(Clone, Copy, Debug)]
+struct TraceRecord {
+    /// The line of the input the turn came from, starting at 1.
+    line: usize,
+    /// The turn, positive to the right.
+    degrees: i32,
+    before: i32,
+    after: i32,
+    /// Whether the turn finished on the target.
+    landed: bool,
+    /// How many times the dial pointed at the target during the turn.
+    crossings: u32,
+}
+
+impl TraceRecord {
+    /// The turn written as it was in the input.
+    fn rotation(&self) -> String {
+        rotation(self.degrees)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TraceFormat {
+    Table,
+    Csv,
+}
+
+impl TraceFormat {
+    fn parse(format: &str) -> Result<Self, String> {
+        match format {
+            "table" => Ok(Self::Table),
+            "csv" => Ok(Self::Csv),
+            format => Err(format!(
+                "Unknown trace format {}, expected table or csv",
+                format
+            )),
+        }
+    }
+}
+
+fn print_trace(records: &[TraceRecord], format: TraceFormat) {
+    let header = ["line", "rotation", "before", "after", "landed", "crossings"];
+    let rows = records.iter().map(|record| {
+        [
+            record.line.to_string(),
+            record.rotation(),
+            record.before.to_string(),
+            record.after.to_string(),
+            record.landed.to_string(),
+            record.crossings.to_string(),
+        ]
     });
-    passed_zero
+    match format {
+        TraceFormat::Csv => {
+            println!("{}", header.join(","));
+            for row in rows {
+                println!("{}", row.join(","));
+            }
+        }
+        TraceFormat::Table => {
+            let rows: Vec<_> = rows.collect();
+            // Numbers are lined up on the right.
+            print_table(&header, &rows, &[Align::Right; 6]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counting_matches_clicking_through() {
+        for seed in 0..10 {
+            let counterexample = run_check(1000, seed);
+            assert!(
+                counterexample.is_none(),
+                "seed {} found {:?}",
+                seed,
+                counterexample
+            );
+        }
+    }
 }
@@ -1,18 +1,56 @@
-use std::{collections::HashSet, fs};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    env, fmt, fs, hint,
+    ops::{Div, Mul, Sub},
+    rc::Rc,
+    time::Instant,
+};
 
 const INPUT_PATH: &str = "inputs/10.txt";
+/// The most buttons that can be left free after elimination when every combination of them needs
+/// to be searched, so much more than this would take far too long.
+const MAX_FREE_BUTTONS: usize = 20;
+/// The most bits the search for the fewest presses can walk through every combination of, either
+/// of the free buttons or of the pivot buttons, whichever is fewer.
+const MAX_SEARCH_BITS: usize = 24;
 
 /// https://adventofcode.com/2025/day/10
+///
+/// Pass `--explain` to also print which buttons to press on each machine, and
+/// `--skip-unsatisfiable` to leave out machines that can't be solved rather than failing. There is
+/// never an answer if we fail to solve a machine, since it might still have a solution.
+/// `--bench` times how long toggling lights takes with [`LightMask`] compared to a [`HashSet`].
 fn main() {
+    let explain = env::args().skip(1).any(|arg| arg == "--explain");
+    let skip_unsatisfiable = env::args().skip(1).any(|arg| arg == "--skip-unsatisfiable");
     let configs = match parse_input(INPUT_PATH) {
         Ok(input) => input,
         Err(e) => return println!("Failed with error: {}", e),
     };
-    // Expected 385
-    match sum_minimum_set_merges(&configs) {
-        Ok(answer) => println!("Answer 1 is: {}", answer),
-        Err(err) => println!("Failed with: {}", err),
+    if env::args().skip(1).any(|arg| arg == "--bench") {
+        return bench_light_representations(&configs);
     }
+    // Expected 385
+    solve_each(&configs, find_minimum_button_presses_to_target).print(
+        1,
+        explain,
+        skip_unsatisfiable,
+        |buttons| format!("press buttons {:?} ({} presses)", buttons, buttons.len()),
+    );
+    // Expected 16757
+    solve_each(&configs, find_minimum_presses_to_requirements).print(
+        2,
+        explain,
+        skip_unsatisfiable,
+        |presses| {
+            format!(
+                "press counts {:?} ({} presses)",
+                presses,
+                presses.iter().sum::<u64>()
+            )
+        },
+    );
 }
 
 /// Input is expected to be a list of configurations, one config per line.
@@ -21,161 +59,1152 @@ fn main() {
 /// The start state is all off.
 /// The following numbers in parenthesis represent buttons, pressing one will toggle
 /// the state at the indices listed in the button.
+/// The final numbers in curly braces are the joltage requirements, one for each light.
 fn parse_input(input_path: &str) -> Result<Vec<Configuration>, String> {
     let input_text = fs::read_to_string(input_path).map_err(|e| e.to_string())?;
     input_text
         .lines()
-        .map(|l| {
-            let mut parts = l.split_whitespace();
-            // TODO parse this properly
-            let target = parts.next().unwrap();
-            let mut parts_from_back = parts.rev();
-            let requirements = parts_from_back.next().unwrap();
-            let buttons: Result<Vec<HashSet<u64>>, String> =
-                parts_from_back.map(parse_button).collect();
-            Ok(Configuration {
-                target: parse_target(target)?,
-                buttons: buttons?,
-                requirements: parse_requirements(requirements)?,
-            })
-        })
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| parse_configuration(l, i + 1).map_err(|e| format!("Line {}: {}", i + 1, e)))
         .collect()
 }
 
-fn parse_target(string: &str) -> Result<HashSet<u64>, String> {
-    let mut target_set = HashSet::new();
-    let mut index = 0;
-    for char in string.chars() {
-        // TODO check properly formed with []
-        match char {
-            '.' => {
-                index += 1;
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+    OpenBracket,
+    CloseBracket,
+    Light(bool),
+    OpenParen,
+    CloseParen,
+    OpenBrace,
+    CloseBrace,
+    Comma,
+    Number(u64),
+}
+
+/// Split a line into tokens, each paired with the column it started at so we can point at
+/// where any problems are.
+fn tokenize(line: &str) -> Result<Vec<(usize, Token)>, String> {
+    let mut tokens = vec![];
+    let mut chars = line.char_indices().peekable();
+    while let Some((column, char)) = chars.next() {
+        let token = match char {
+            '[' => Token::OpenBracket,
+            ']' => Token::CloseBracket,
+            '.' => Token::Light(false),
+            '#' => Token::Light(true),
+            '(' => Token::OpenParen,
+            ')' => Token::CloseParen,
+            '{' => Token::OpenBrace,
+            '}' => Token::CloseBrace,
+            ',' => Token::Comma,
+            digit if digit.is_ascii_digit() => {
+                let mut end = column + 1;
+                while let Some(&(next_column, next)) = chars.peek()
+                    && next.is_ascii_digit()
+                {
+                    end = next_column + 1;
+                    chars.next();
+                }
+                let number = line[column..end]
+                    .parse::<u64>()
+                    .map_err(|e| format!("column {}: {}", column + 1, e))?;
+                Token::Number(number)
             }
-            '#' => {
-                target_set.insert(index);
-                index += 1;
+            c if c.is_whitespace() => continue,
+            c => {
+                return Err(format!(
+                    "column {}: unexpected character '{}'",
+                    column + 1,
+                    c
+                ));
+            }
+        };
+        tokens.push((column, token));
+    }
+    Ok(tokens)
+}
+
+/// Parses a single line following the grammar:
+///   config   := '[' light* ']' button* '{' numbers '}'
+///   button   := '(' numbers ')'
+///   numbers  := number (',' number)*
+fn parse_configuration(line: &str, line_number: usize) -> Result<Configuration, String> {
+    let tokens = tokenize(line)?;
+    let mut parser = Parser {
+        tokens: &tokens,
+        position: 0,
+        line_length: line.len(),
+    };
+
+    parser.expect(Token::OpenBracket)?;
+    let mut lights = vec![];
+    while let Some(Token::Light(on)) = parser.peek() {
+        parser.next();
+        lights.push(on);
+    }
+    parser.expect(Token::CloseBracket)?;
+    let light_count = lights.len();
+    let mut target = LightMask::empty(light_count);
+    for (light, _) in lights.iter().enumerate().filter(|(_, on)| **on) {
+        target.insert(light);
+    }
+
+    let mut buttons = vec![];
+    while parser.peek() == Some(Token::OpenParen) {
+        let column = parser.column();
+        parser.next();
+        let indices = parser.numbers(Token::CloseParen)?;
+        let mut button = LightMask::empty(light_count);
+        for index in indices {
+            if index >= light_count as u64 {
+                return Err(format!(
+                    "column {}: button index {} is out of range for {} lights",
+                    column + 1,
+                    index,
+                    light_count
+                ));
             }
-            _ => {}
+            button.insert(index as usize);
         }
+        buttons.push(button);
+    }
+
+    let column = parser.column();
+    parser.expect(Token::OpenBrace)?;
+    let requirements = parser.numbers(Token::CloseBrace)?;
+    if requirements.len() != light_count {
+        return Err(format!(
+            "column {}: found {} joltage requirements for {} lights",
+            column + 1,
+            requirements.len(),
+            light_count
+        ));
+    }
+    if let Some((column, token)) = parser.tokens.get(parser.position) {
+        return Err(format!(
+            "column {}: unexpected {:?} after joltage requirements",
+            column + 1,
+            token
+        ));
     }
-    Ok(target_set)
+
+    Ok(Configuration {
+        line: line_number,
+        target,
+        buttons,
+        requirements,
+    })
 }
 
-fn parse_button(string: &str) -> Result<HashSet<u64>, String> {
-    let mut button_indices = HashSet::new();
-    for char in string.chars() {
-        // TODO check properly formed with []
-        match char {
-            digit if digit.is_ascii_digit() => {
-                let index = digit.to_digit(10).ok_or("Failed to parse digit")?;
-                button_indices.insert(index as u64);
+struct Parser<'a> {
+    tokens: &'a [(usize, Token)],
+    position: usize,
+    line_length: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.position).map(|&(_, token)| token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek();
+        self.position += 1;
+        token
+    }
+
+    // The column of the next token, or the end of the line if there are none left.
+    fn column(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map_or(self.line_length, |&(column, _)| column)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        let column = self.column();
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!(
+                "column {}: expected {:?} but found {:?}",
+                column + 1,
+                expected,
+                token
+            )),
+            None => Err(format!(
+                "column {}: expected {:?} but reached the end of the line",
+                column + 1,
+                expected
+            )),
+        }
+    }
+
+    // A non empty comma separated list of numbers, finishing with the given closing token.
+    fn numbers(&mut self, close: Token) -> Result<Vec<u64>, String> {
+        let mut numbers = vec![];
+        loop {
+            let column = self.column();
+            match self.next() {
+                Some(Token::Number(number)) => numbers.push(number),
+                Some(token) => {
+                    return Err(format!(
+                        "column {}: expected a number but found {:?}",
+                        column + 1,
+                        token
+                    ));
+                }
+                None => {
+                    return Err(format!(
+                        "column {}: expected a number but reached the end of the line",
+                        column + 1
+                    ));
+                }
+            }
+            if self.peek() == Some(close) {
+                self.next();
+                return Ok(numbers);
             }
-            _ => {}
+            self.expect(Token::Comma)?;
         }
     }
-    Ok(button_indices)
 }
 
-fn parse_requirements(string: &str) -> Result<HashSet<u64>, String> {
-    let mut requirements = HashSet::new();
-    for char in string.chars() {
-        // TODO check properly formed with []
-        match char {
-            digit if digit.is_ascii_digit() => {
-                let index = digit.to_digit(10).ok_or("Failed to parse digit")?;
-                requirements.insert(index as u64);
+/// Try every combination of buttons on every machine, the way lights used to be solved, once
+/// toggling [`LightMask`]s and once taking the symmetric difference of [`HashSet`]s of light
+/// indices, which is how lights used to be stored.
+fn bench_light_representations(configs: &[Configuration]) {
+    // Every combination is visited in Gray code order, so each step presses or unpresses one button.
+    const MAX_BUTTONS: usize = 20;
+    let configs: Vec<_> = configs
+        .iter()
+        .filter(|c| c.buttons.len() <= MAX_BUTTONS)
+        .collect();
+
+    let start = Instant::now();
+    let mut mask_matches = 0;
+    for config in &configs {
+        let mut lights = LightMask::empty(config.light_count());
+        for step in 1..1_u64 << config.buttons.len() {
+            lights.toggle(&config.buttons[step.trailing_zeros() as usize]);
+            if hint::black_box(&lights) == &config.target {
+                mask_matches += 1;
             }
-            _ => {}
         }
     }
-    Ok(requirements)
+    let mask_time = start.elapsed();
+
+    let as_sets = |mask: &LightMask| -> HashSet<u64> {
+        mask.ones().into_iter().map(|light| light as u64).collect()
+    };
+    let set_configs: Vec<(HashSet<u64>, Vec<HashSet<u64>>)> = configs
+        .iter()
+        .map(|c| (as_sets(&c.target), c.buttons.iter().map(as_sets).collect()))
+        .collect();
+    let start = Instant::now();
+    let mut set_matches = 0;
+    for (target, buttons) in &set_configs {
+        let mut lights = HashSet::new();
+        for step in 1..1_u64 << buttons.len() {
+            lights = lights
+                .symmetric_difference(&buttons[step.trailing_zeros() as usize])
+                .copied()
+                .collect();
+            if hint::black_box(&lights) == target {
+                set_matches += 1;
+            }
+        }
+    }
+    let set_time = start.elapsed();
+
+    println!(
+        "Searched every combination of buttons on {} machines with at most {} buttons",
+        configs.len(),
+        MAX_BUTTONS
+    );
+    println!("LightMask: {:?} ({} matches)", mask_time, mask_matches);
+    println!("HashSet:   {:?} ({} matches)", set_time, set_matches);
+}
+
+/// Solve every machine on its own, so that one that can't be solved, or that we fail to solve,
+/// doesn't stop us from solving the rest.
+fn solve_each<T: Presses>(
+    configs: &[Configuration],
+    solve: fn(&Configuration) -> Result<Result<T, Unsatisfiable>, String>,
+) -> Summary<T> {
+    let mut summary = Summary {
+        solved: vec![],
+        unsatisfiable: vec![],
+        failed: vec![],
+    };
+    for config in configs {
+        match solve(config) {
+            Ok(Ok(presses)) => summary.solved.push((config.line, presses)),
+            Ok(Err(reason)) => summary.unsatisfiable.push((config.line, reason)),
+            Err(err) => summary.failed.push((config.line, err)),
+        }
+    }
+    summary
+}
+
+/// Anything that can count the total number of button presses it is made up of.
+trait Presses {
+    fn total_presses(&self) -> u64;
+}
+
+impl Presses for Vec<usize> {
+    fn total_presses(&self) -> u64 {
+        self.len() as u64
+    }
 }
 
-fn sum_minimum_set_merges(configs: &[Configuration]) -> Result<u64, String> {
-    let results: Vec<u64> = configs
+impl Presses for Vec<u64> {
+    fn total_presses(&self) -> u64 {
+        self.iter().sum()
+    }
+}
+
+/// The result of solving every machine for one part, by line number.
+struct Summary<T> {
+    solved: Vec<(usize, T)>,
+    unsatisfiable: Vec<(usize, Unsatisfiable)>,
+    // Machines we couldn't get an answer for either way, such as ones with too many free buttons.
+    failed: Vec<(usize, String)>,
+}
+
+impl<T: Presses> Summary<T> {
+    /// The total presses over every machine. Machines we failed to solve might still have a
+    /// solution, so there is no total without them even when skipping the unsatisfiable ones.
+    fn total(&self, skip_unsatisfiable: bool) -> Result<u64, String> {
+        if !self.failed.is_empty() {
+            return Err(format!(
+                "failed to solve {} machine(s), and found {} unsatisfiable",
+                self.failed.len(),
+                self.unsatisfiable.len()
+            ));
+        }
+        if !self.unsatisfiable.is_empty() && !skip_unsatisfiable {
+            return Err(format!(
+                "found {} unsatisfiable machine(s), pass --skip-unsatisfiable to skip them",
+                self.unsatisfiable.len()
+            ));
+        }
+        Ok(self.solved.iter().map(|(_, p)| p.total_presses()).sum())
+    }
+
+    /// Print the answer followed by the machines that couldn't be solved, along with the presses
+    /// for every solved machine if `explain` is set.
+    fn print(
+        &self,
+        part: u32,
+        explain: bool,
+        skip_unsatisfiable: bool,
+        describe: fn(&T) -> String,
+    ) {
+        match self.total(skip_unsatisfiable) {
+            Ok(total) => println!("Answer {} is: {}", part, total),
+            Err(e) => println!("Failed with: {}", e),
+        }
+        if explain {
+            for (line, presses) in &self.solved {
+                println!("  Line {}: {}", line, describe(presses));
+            }
+        }
+        for (line, reason) in &self.unsatisfiable {
+            println!("  Line {} is unsatisfiable: {}", line, reason);
+        }
+        for (line, err) in &self.failed {
+            println!("  Line {} failed with: {}", line, err);
+        }
+    }
+}
+
+/// Why no combination of button presses can configure a machine.
+#[derive(Clone, Debug)]
+enum Unsatisfiable {
+    /// Every button toggles an even number of these lights, so an even number of them are always
+    /// on, but an odd number of them need to be.
+    Lights(Vec<usize>),
+    /// Every button adds an even amount to the total of these counters, so their total is always
+    /// even, but their requirements add up to an odd number.
+    Joltage(Vec<usize>),
+    /// Weighting each counter by these amounts, every button adds zero or more to the weighted
+    /// total, but the weighted requirements add up to less than zero. So the requirements can't be
+    /// met even pressing buttons fractions of a time.
+    Weighted { weights: Vec<i64>, total: i64 },
+    /// Searching every way of meeting the requirements one bit at a time found nothing. This only
+    /// happens when they could be met by pressing buttons fractions of a time, so no weighting of
+    /// the counters rules them out, and unlike the others it is only the result of the search, not
+    /// a certificate that can be checked on its own.
+    Exhausted,
+}
+
+impl fmt::Display for Unsatisfiable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unsatisfiable::Lights(lights) => write!(
+                f,
+                "every button toggles an even number of lights {:?}, but an odd number of them need to be on",
+                lights
+            ),
+            Unsatisfiable::Joltage(counters) => write!(
+                f,
+                "every button adds an even amount to the total of counters {:?}, but their requirements add up to an odd number",
+                counters
+            ),
+            Unsatisfiable::Weighted { weights, total } => write!(
+                f,
+                "weighting the counters by {:?}, every button adds zero or more, but the requirements add up to {}",
+                weights, total
+            ),
+            Unsatisfiable::Exhausted => {
+                write!(
+                    f,
+                    "searching every combination of presses found none that meets the requirements"
+                )
+            }
+        }
+    }
+}
+
+/// Pressing a button twice is the same as not pressing it at all, so we only ever need to press
+/// each button once or not at all. Which buttons to press is then a system of linear equations over
+/// GF(2), with one equation per light and one unknown per button. We solve this with Gaussian
+/// elimination, then only need to search the null space for the solution pressing fewest buttons,
+/// see [`SolutionSpace::fewest_presses`].
+///
+/// Returns the indices of the buttons to press.
+fn find_minimum_button_presses_to_target(
+    config: &Configuration,
+) -> Result<Result<Vec<usize>, Unsatisfiable>, String> {
+    let solutions =
+        match SolutionSpace::solve(&config.buttons, config.light_count(), &config.target)? {
+            Ok(solutions) => solutions,
+            Err(lights) => return Ok(Err(Unsatisfiable::Lights(lights))),
+        };
+    let pressed = solutions.fewest_presses()?.ones();
+    if config.toggle_lights(&pressed) != config.target {
+        return Err(format!(
+            "Pressing buttons {:?} does not reach the target {:?}",
+            pressed,
+            config.target.ones()
+        ));
+    }
+    Ok(Ok(pressed))
+}
+
+/// Finding the fewest presses to hit the joltage requirements exactly is an integer linear program,
+/// but it can be solved exactly by looking at it one bit at a time.
+///
+/// Take any set of press counts that works. The buttons pressed an odd number of times must on
+/// their own get the parity of every counter right, and once we take one press of each of those
+/// away, everything left over is pressed an even number of times. So that remainder is just twice
+/// a solution for half of what is left of the requirements. This means we can try every set of
+/// buttons with the right parity, halve what is left and recurse, and the target roughly halves at
+/// each step so we only go a handful of levels deep.
+///
+/// Returns how many times to press each button.
+fn find_minimum_presses_to_requirements(
+    config: &Configuration,
+) -> Result<Result<Vec<u64>, Unsatisfiable>, String> {
+    // If we can't even get the parity of each counter right we can say exactly why.
+    let parity = odd_counters(&config.requirements);
+    if let Err(counters) = SolutionSpace::solve(&config.buttons, config.light_count(), &parity)? {
+        return Ok(Err(Unsatisfiable::Joltage(counters)));
+    }
+    let mut memo = HashMap::new();
+    let mut combinations_by_parity = HashMap::new();
+    let button_counters: Vec<Vec<usize>> = config.buttons.iter().map(LightMask::ones).collect();
+    let Some((_, presses)) = minimum_presses_for(
+        &config.requirements,
+        &config.buttons,
+        &button_counters,
+        &mut combinations_by_parity,
+        &mut memo,
+    )?
+    else {
+        return Ok(Err(match counter_weights(config) {
+            Some((weights, total)) => Unsatisfiable::Weighted { weights, total },
+            None => Unsatisfiable::Exhausted,
+        }));
+    };
+    if config.add_joltage(&presses) != config.requirements {
+        return Err(format!(
+            "Press counts {:?} do not reach the requirements {:?}",
+            presses, config.requirements
+        ));
+    }
+    Ok(Ok(presses))
+}
+
+/// Weights for the counters proving the requirements can't be met, along with the weighted total of
+/// the requirements, as described by [`Unsatisfiable::Weighted`]. None if the requirements can be
+/// met pressing buttons fractions of a time.
+///
+/// This is the first phase of the simplex method, which looks for any fractional presses meeting
+/// the requirements by minimising how far short of them we fall. If we can't get that down to
+/// zero, how much each requirement is worth at the optimum gives the weights, negated.
+fn counter_weights(config: &Configuration) -> Option<(Vec<i64>, i64)> {
+    let counters = config.requirements.len();
+    let buttons = config.buttons.len();
+    let columns = buttons + counters;
+    // One row per counter, with a column for each button, then one for how far short of the
+    // requirement we fall, then the requirement. Those shortfalls make up the starting basis.
+    let mut rows: Vec<Vec<Fraction>> = (0..counters)
+        .map(|counter| {
+            let mut row = vec![Fraction::from(0); columns + 1];
+            for (button, _) in config
+                .buttons
+                .iter()
+                .enumerate()
+                .filter(|(_, b)| b.contains(counter))
+            {
+                row[button] = Fraction::from(1);
+            }
+            row[buttons + counter] = Fraction::from(1);
+            row[columns] = Fraction::from(config.requirements[counter] as i128);
+            row
+        })
+        .collect();
+    let mut basis: Vec<usize> = (buttons..columns).collect();
+    // How much the total shortfall changes for each unit of a column, and the total shortfall.
+    let mut costs = vec![Fraction::from(0); columns + 1];
+    for row in &rows {
+        for (column, value) in row.iter().enumerate() {
+            if column < buttons || column == columns {
+                costs[column] = costs[column] - *value;
+            }
+        }
+    }
+
+    // Always picking the first column and row that can improve things means we never cycle.
+    while let Some(entering) = (0..columns).find(|&column| costs[column] < Fraction::from(0)) {
+        let leaving = (0..counters)
+            .filter(|&row| rows[row][entering] > Fraction::from(0))
+            .min_by(|&a, &b| {
+                let ratio = |row: usize| rows[row][columns] / rows[row][entering];
+                ratio(a).cmp(&ratio(b)).then(basis[a].cmp(&basis[b]))
+            })?;
+        let pivot = rows[leaving][entering];
+        for value in rows[leaving].iter_mut() {
+            *value = *value / pivot;
+        }
+        let pivot_row = rows[leaving].clone();
+        for (r, row) in rows.iter_mut().enumerate() {
+            let factor = row[entering];
+            if r != leaving && factor != Fraction::from(0) {
+                for (value, &p) in row.iter_mut().zip(&pivot_row) {
+                    *value = *value - factor * p;
+                }
+            }
+        }
+        let factor = costs[entering];
+        for (value, &p) in costs.iter_mut().zip(&pivot_row) {
+            *value = *value - factor * p;
+        }
+        basis[leaving] = entering;
+    }
+    if costs[columns] == Fraction::from(0) {
+        return None;
+    }
+
+    // The shortfall columns started as the identity, so their costs give the value of each
+    // requirement, as each one costs 1 less the value of what it stands in for.
+    let values: Vec<Fraction> = (0..counters)
+        .map(|counter| costs[buttons + counter] - Fraction::from(1))
+        .collect();
+    let scale = values
+        .iter()
+        .fold(1, |scale, value| lcm(scale, value.denominator));
+    let weights: Vec<i64> = values
         .iter()
-        .map(find_minimum_button_presses_to_target)
-        .collect::<Result<Vec<u64>, String>>()?;
-    Ok(results.iter().sum())
+        .map(|value| i64::try_from(value.numerator * (scale / value.denominator)).ok())
+        .collect::<Option<_>>()?;
+    let total: i64 = weights
+        .iter()
+        .zip(&config.requirements)
+        .map(|(w, &r)| w * r as i64)
+        .sum();
+    // Check the proof holds before relying on it.
+    let every_button_adds = config
+        .buttons
+        .iter()
+        .all(|button| button.ones().iter().map(|&c| weights[c]).sum::<i64>() >= 0);
+    (every_button_adds && total < 0).then_some((weights, total))
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+fn lcm(a: i128, b: i128) -> i128 {
+    a / gcd(a, b) * b
 }
 
-fn find_minimum_button_presses_to_target(config: &Configuration) -> Result<u64, String> {
-    for merge_size in 1..=config.buttons.len() {
-        let merge_sets = all_combinations_for_size(&config.buttons, merge_size);
-        if merge_sets.iter().any(|s| s.set_equals(&config.target)) {
-            return Ok(merge_size as u64);
+/// An exact fraction, kept in lowest terms with a positive denominator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Fraction {
+    numerator: i128,
+    denominator: i128,
+}
+
+impl Fraction {
+    fn new(numerator: i128, denominator: i128) -> Self {
+        let divisor = gcd(numerator, denominator) * denominator.signum();
+        Self {
+            numerator: numerator / divisor,
+            denominator: denominator / divisor,
         }
     }
-    Err(format!(
-        "Failed to find working set for config with target {:?}",
-        config.target
-    ))
 }
 
-fn all_combinations_for_size(buttons: &[HashSet<u64>], target_size: usize) -> Vec<MergeSet> {
-    let mut merge_sets = vec![];
-    for i in 0..=(buttons.len() - target_size) {
-        let starting_set = MergeSet::new(buttons[i].clone());
-        merge_sets.extend(recursive_merge_from(
-            starting_set,
-            buttons,
-            i + 1,
-            target_size,
-        ))
-    }
-    merge_sets
-}
-
-fn recursive_merge_from(
-    starting_set: MergeSet,
-    buttons: &[HashSet<u64>],
-    start_index: usize,
-    target_size: usize,
-) -> Vec<MergeSet> {
-    if starting_set.size >= target_size {
-        return vec![starting_set];
-    }
-    let left_to_merge = target_size - starting_set.size;
-    let mut merge_sets = vec![];
-    for i in start_index..=(buttons.len() - left_to_merge) {
-        merge_sets.extend(recursive_merge_from(
-            starting_set.merge(&buttons[i]),
+impl From<i128> for Fraction {
+    fn from(value: i128) -> Self {
+        Self::new(value, 1)
+    }
+}
+
+impl Sub for Fraction {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(
+            self.numerator * other.denominator - other.numerator * self.denominator,
+            self.denominator * other.denominator,
+        )
+    }
+}
+
+impl Mul for Fraction {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self::new(
+            self.numerator * other.numerator,
+            self.denominator * other.denominator,
+        )
+    }
+}
+
+impl Div for Fraction {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        Self::new(
+            self.numerator * other.denominator,
+            self.denominator * other.numerator,
+        )
+    }
+}
+
+impl Ord for Fraction {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.numerator * other.denominator).cmp(&(other.numerator * self.denominator))
+    }
+}
+
+impl PartialOrd for Fraction {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// The counters with an odd requirement.
+fn odd_counters(requirements: &[u64]) -> LightMask {
+    let mut odd = LightMask::empty(requirements.len());
+    for (counter, _) in requirements.iter().enumerate().filter(|(_, r)| *r % 2 == 1) {
+        odd.insert(counter);
+    }
+    odd
+}
+
+fn minimum_presses_for(
+    requirements: &[u64],
+    buttons: &[LightMask],
+    // The counters each button adds to.
+    button_counters: &[Vec<usize>],
+    // Every combination of buttons, each pressed once, that leaves exactly the given counters odd,
+    // None if there aren't any. Filled in as we go, since most parities never come up.
+    combinations_by_parity: &mut HashMap<LightMask, Option<Rc<SolutionSpace>>>,
+    // The fewest total presses for requirements we have already seen along with the presses of each
+    // button, None if they can't be met.
+    memo: &mut HashMap<Vec<u64>, Option<(u64, Vec<u64>)>>,
+) -> Result<Option<(u64, Vec<u64>)>, String> {
+    if requirements.iter().all(|&r| r == 0) {
+        return Ok(Some((0, vec![0; buttons.len()])));
+    }
+    if let Some(known) = memo.get(requirements) {
+        return Ok(known.clone());
+    }
+    let parity = odd_counters(requirements);
+    let combinations = match combinations_by_parity.get(&parity) {
+        Some(combinations) => combinations.clone(),
+        None => {
+            let combinations = SolutionSpace::solve(buttons, requirements.len(), &parity)?
+                .ok()
+                .map(Rc::new);
+            combinations_by_parity.insert(parity, combinations.clone());
+            combinations
+        }
+    };
+    let mut fewest: Option<(u64, Vec<u64>)> = None;
+    let all_pressed = combinations
+        .as_deref()
+        .map(SolutionSpace::all_solutions)
+        .transpose()?;
+    for pressed in all_pressed.into_iter().flatten() {
+        let mut increments = vec![0; requirements.len()];
+        for button in pressed.ones() {
+            for &counter in &button_counters[button] {
+                increments[counter] += 1;
+            }
+        }
+        if increments.iter().zip(requirements).any(|(i, r)| i > r) {
+            continue;
+        }
+        let halved: Vec<u64> = requirements
+            .iter()
+            .zip(&increments)
+            .map(|(r, i)| (r - i) / 2)
+            .collect();
+        if let Some((rest, rest_presses)) = minimum_presses_for(
+            &halved,
             buttons,
-            i + 1,
-            target_size,
-        ));
+            button_counters,
+            combinations_by_parity,
+            memo,
+        )? {
+            let total = pressed.count_ones() as u64 + 2 * rest;
+            if fewest.as_ref().is_none_or(|(f, _)| total < *f) {
+                let presses = rest_presses
+                    .iter()
+                    .enumerate()
+                    .map(|(i, rest)| pressed.contains(i) as u64 + 2 * rest)
+                    .collect();
+                fewest = Some((total, presses));
+            }
+        }
     }
-    merge_sets
+    memo.insert(requirements.to_vec(), fewest.clone());
+    Ok(fewest)
 }
 
 struct Configuration {
-    target: HashSet<u64>,
-    buttons: Vec<HashSet<u64>>,
-    requirements: HashSet<u64>,
+    // The line of the input this came from, counting from 1.
+    line: usize,
+    target: LightMask,
+    buttons: Vec<LightMask>,
+    requirements: Vec<u64>,
 }
 
-// A small wrapper around a set to keep track of how many sets it has been merged with.
-// Merging in this context is the symmetric difference between two sets, that is merging
-// two sets will return a set that has all the elements in one or the other but not both.
-struct MergeSet {
-    set: HashSet<u64>,
-    size: usize,
+impl Configuration {
+    fn light_count(&self) -> usize {
+        // There is one joltage requirement for every light.
+        self.requirements.len()
+    }
+
+    /// Replay pressing each of the given buttons once from all lights off, returning which lights
+    /// end up on.
+    fn toggle_lights(&self, pressed: &[usize]) -> LightMask {
+        let mut lights = LightMask::empty(self.light_count());
+        for &button in pressed {
+            lights.toggle(&self.buttons[button]);
+        }
+        lights
+    }
+
+    /// Replay pressing each button the given number of times, returning the joltage of each counter.
+    fn add_joltage(&self, presses: &[u64]) -> Vec<u64> {
+        let mut joltage = vec![0; self.requirements.len()];
+        for (button, &count) in self.buttons.iter().zip(presses) {
+            for counter in button.ones() {
+                joltage[counter] += count;
+            }
+        }
+        joltage
+    }
 }
 
-impl MergeSet {
-    fn new(set: HashSet<u64>) -> Self {
-        Self { set, size: 1 }
+/// All the sets of buttons that can be pressed once each to get from all lights off to the target.
+/// Each set is stored as a bitmask with bit i set when button i is pressed, and every solution is
+/// the particular solution XORed with some combination of the null space basis.
+struct SolutionSpace {
+    particular: LightMask,
+    null_space: Vec<LightMask>,
+    // The button each row of the reduced equations was solved for. The particular solution only
+    // ever presses these.
+    pivot_buttons: Vec<usize>,
+}
+
+impl SolutionSpace {
+    /// If there is no way to turn on exactly the lights in `lights_on`, returns a set of lights
+    /// that every button toggles an even number of, but where an odd number need to be on.
+    fn solve(
+        buttons: &[LightMask],
+        light_count: usize,
+        lights_on: &LightMask,
+    ) -> Result<Result<Self, Vec<usize>>, String> {
+        let button_count = buttons.len();
+        // One row per light, with bit i set when button i toggles it, along with whether the
+        // light needs to end up on. We also keep track of which of the original lights each row
+        // has been combined from, so if one turns out impossible we can say which lights are
+        // to blame.
+        let mut rows: Vec<(LightMask, bool, LightMask)> = (0..light_count)
+            .map(|light| {
+                let mut toggled_by = LightMask::empty(button_count);
+                for (i, _) in buttons
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, b)| b.contains(light))
+                {
+                    toggled_by.insert(i);
+                }
+                let mut combined_from = LightMask::empty(light_count);
+                combined_from.insert(light);
+                (toggled_by, lights_on.contains(light), combined_from)
+            })
+            .collect();
+
+        // Reduce to reduced row echelon form, remembering which button each pivot row is for.
+        let mut pivot_buttons = vec![];
+        for button in 0..button_count {
+            let rank = pivot_buttons.len();
+            let Some(pivot) = (rank..rows.len()).find(|&r| rows[r].0.contains(button)) else {
+                continue;
+            };
+            rows.swap(rank, pivot);
+            let (pivot_mask, pivot_on, pivot_from) = rows[rank].clone();
+            for (r, row) in rows.iter_mut().enumerate() {
+                if r != rank && row.0.contains(button) {
+                    row.0.toggle(&pivot_mask);
+                    row.1 ^= pivot_on;
+                    row.2.toggle(&pivot_from);
+                }
+            }
+            pivot_buttons.push(button);
+        }
+        // Any row left with no buttons but a light that needs to be on can never be satisfied.
+        if let Some((_, _, combined_from)) =
+            rows[pivot_buttons.len()..].iter().find(|(_, on, _)| *on)
+        {
+            return Ok(Err(combined_from.ones()));
+        }
+
+        let mut particular = LightMask::empty(button_count);
+        for (&button, _) in pivot_buttons.iter().zip(&rows).filter(|(_, row)| row.1) {
+            particular.insert(button);
+        }
+        // Each button without a pivot is free to be pressed, as long as we also flip the pivot
+        // buttons whose rows it appears in.
+        let null_space = (0..button_count)
+            .filter(|button| !pivot_buttons.contains(button))
+            .map(|free| {
+                let mut vector = LightMask::empty(button_count);
+                vector.insert(free);
+                for (&button, _) in pivot_buttons
+                    .iter()
+                    .zip(&rows)
+                    .filter(|(_, row)| row.0.contains(free))
+                {
+                    vector.insert(button);
+                }
+                vector
+            })
+            .collect();
+        Ok(Ok(Self {
+            particular,
+            null_space,
+            pivot_buttons,
+        }))
     }
 
-    fn merge(&self, other: &HashSet<u64>) -> MergeSet {
-        let merged = self.set.symmetric_difference(other).copied().collect();
-        MergeSet {
-            set: merged,
-            size: self.size + 1,
+    /// The solution pressing the fewest buttons.
+    ///
+    /// Each null space vector presses its own free button along with some of the pivot buttons,
+    /// so a solution presses each free button chosen plus whichever pivot buttons are left pressed
+    /// once the particular solution is combined with them. When there are fewer free buttons than
+    /// pivots we simply try every combination of them. Otherwise we search outwards from the
+    /// particular solution through the combinations of pivot buttons, one free button at a time,
+    /// which finds the fewest free buttons needed to reach each of them.
+    fn fewest_presses(&self) -> Result<LightMask, String> {
+        let free_count = self.null_space.len();
+        let pivot_count = self.pivot_buttons.len();
+        if free_count.min(pivot_count) > MAX_SEARCH_BITS {
+            return Err(format!(
+                "Too many buttons to search through: {} free and {} pivots (at most {} of either)",
+                free_count, pivot_count, MAX_SEARCH_BITS
+            ));
         }
+        if free_count <= pivot_count {
+            return Ok(self
+                .gray_code_solutions()
+                .min_by_key(LightMask::count_ones)
+                .expect("there is always at least the particular solution"));
+        }
+
+        // The pivot buttons pressed by a combination, with bit i set for `pivot_buttons[i]`.
+        let pivots_pressed = |presses: &LightMask| -> usize {
+            (0..pivot_count)
+                .filter(|&i| presses.contains(self.pivot_buttons[i]))
+                .fold(0, |bits, i| bits | 1 << i)
+        };
+        let free_pivots: Vec<usize> = self.null_space.iter().map(pivots_pressed).collect();
+        // For each combination of pivot buttons, the last free button on the shortest way to it,
+        // plus one so that zero can mean we haven't reached it yet.
+        let mut reached_by = vec![0; 1 << pivot_count];
+        let mut free_used = vec![0; 1 << pivot_count];
+        let mut queue = std::collections::VecDeque::from([0]);
+        reached_by[0] = usize::MAX;
+        while let Some(pivots) = queue.pop_front() {
+            for (free, &toggled) in free_pivots.iter().enumerate() {
+                let next = pivots ^ toggled;
+                if reached_by[next] == 0 {
+                    reached_by[next] = free + 1;
+                    free_used[next] = free_used[pivots] + 1;
+                    queue.push_back(next);
+                }
+            }
+        }
+        let particular = pivots_pressed(&self.particular);
+        let (mut pivots, _) = (0..1 << pivot_count)
+            .filter(|&pivots| reached_by[pivots] != 0)
+            .map(|pivots: usize| {
+                let presses = free_used[pivots] + (particular ^ pivots).count_ones();
+                (pivots, presses)
+            })
+            .min_by_key(|&(_, presses)| presses)
+            .expect("the particular solution is always reachable");
+        let mut presses = self.particular.clone();
+        while pivots != 0 {
+            let free = reached_by[pivots] - 1;
+            presses.toggle(&self.null_space[free]);
+            pivots ^= free_pivots[free];
+        }
+        Ok(presses)
+    }
+
+    /// Every solution, walking through the combinations of the null space in Gray code order so
+    /// that each step only needs to toggle one basis vector.
+    fn all_solutions(&self) -> Result<impl Iterator<Item = LightMask> + '_, String> {
+        if self.null_space.len() > MAX_FREE_BUTTONS {
+            return Err(format!(
+                "Too many free buttons to search through: {} (at most {})",
+                self.null_space.len(),
+                MAX_FREE_BUTTONS
+            ));
+        }
+        Ok(self.gray_code_solutions())
+    }
+
+    fn gray_code_solutions(&self) -> impl Iterator<Item = LightMask> + '_ {
+        let mut presses = self.particular.clone();
+        let rest = (1..1_u64 << self.null_space.len()).map(move |step| {
+            presses.toggle(&self.null_space[step.trailing_zeros() as usize]);
+            presses.clone()
+        });
+        std::iter::once(self.particular.clone()).chain(rest)
+    }
+}
+
+/// A set of lights stored as bits, with bit i set when light i is in the set. Up to 128 lights fit
+/// in a single integer, so toggling and comparing masks are single instructions, and anything
+/// bigger falls back to a vector of words. The same type is also used for sets of buttons.
+///
+/// Masks are only ever combined with others made for the same number of lights.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum LightMask {
+    Small(u64),
+    Medium(u128),
+    Large(Vec<u64>),
+}
+
+impl LightMask {
+    /// An empty mask with room for `len` lights.
+    fn empty(len: usize) -> Self {
+        if len <= u64::BITS as usize {
+            LightMask::Small(0)
+        } else if len <= u128::BITS as usize {
+            LightMask::Medium(0)
+        } else {
+            LightMask::Large(vec![0; len.div_ceil(u64::BITS as usize)])
+        }
+    }
+
+    /// The most lights this mask has room for.
+    fn capacity(&self) -> usize {
+        match self {
+            LightMask::Small(_) => u64::BITS as usize,
+            LightMask::Medium(_) => u128::BITS as usize,
+            LightMask::Large(words) => words.len() * u64::BITS as usize,
+        }
+    }
+
+    fn contains(&self, light: usize) -> bool {
+        match self {
+            LightMask::Small(bits) => bits >> light & 1 != 0,
+            LightMask::Medium(bits) => bits >> light & 1 != 0,
+            LightMask::Large(words) => words[light / 64] >> (light % 64) & 1 != 0,
+        }
+    }
+
+    fn insert(&mut self, light: usize) {
+        match self {
+            LightMask::Small(bits) => *bits |= 1 << light,
+            LightMask::Medium(bits) => *bits |= 1 << light,
+            LightMask::Large(words) => words[light / 64] |= 1 << (light % 64),
+        }
+    }
+
+    /// Toggle every light in `other`, that is the symmetric difference of the two sets.
+    fn toggle(&mut self, other: &LightMask) {
+        match (self, other) {
+            (LightMask::Small(bits), LightMask::Small(other)) => *bits ^= other,
+            (LightMask::Medium(bits), LightMask::Medium(other)) => *bits ^= other,
+            (LightMask::Large(words), LightMask::Large(other)) => {
+                for (word, other) in words.iter_mut().zip(other) {
+                    *word ^= other;
+                }
+            }
+            _ => panic!("Tried to combine light masks of different sizes"),
+        }
+    }
+
+    fn count_ones(&self) -> u32 {
+        match self {
+            LightMask::Small(bits) => bits.count_ones(),
+            LightMask::Medium(bits) => bits.count_ones(),
+            LightMask::Large(words) => words.iter().map(|w| w.count_ones()).sum(),
+        }
+    }
+
+    /// The index of every light in the set, in increasing order.
+    fn ones(&self) -> Vec<usize> {
+        (0..self.capacity()).filter(|&i| self.contains(i)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configuration(line: &str) -> Configuration {
+        parse_configuration(line, 1).expect("line should parse")
+    }
+
+    #[test]
+    fn fewest_presses_with_many_free_buttons() {
+        // 35 buttons for 12 lights leaves 23 of them free.
+        let config = configuration(
+            "[##..#.##..##] (0,5,8,11) (0,1,2,3,10) (3,6,7,8) (3,9) (3,11) (1,2,4,6,10) (7,9,11) \
+             (0,2,10) (2,3,10) (3,5,8,10) (3,6,11) (0,2,5,6) (1,4,5) (0,5,9,11) (4,5) (2,5,7,11) \
+             (0,2,4,9,11) (0,6,8,11) (0,6,7,9) (2,11) (1,3,7) (4,5,8,11) (0,1,4,5,9) (1,3,5,8,9) \
+             (4,5,8) (4,10) (1,2,4,10) (0,1,2,7) (0,3,4,5,9) (0,2,6,8,10) (2,3,5,6,9) (2,6) \
+             (0,2,4,6) (2,7,9) (4,5,7,8,11) {53,36,66,49,35,50,31,30,25,39,39,32}",
+        );
+        let pressed = find_minimum_button_presses_to_target(&config)
+            .expect("search shouldn't give up")
+            .expect("lights can be configured");
+        assert_eq!(pressed.len(), 4);
+        assert_eq!(config.toggle_lights(&pressed), config.target);
+    }
+
+    #[test]
+    fn weights_prove_requirements_cant_be_met() {
+        let config = configuration("[.#] (0,1) {2,0}");
+        let Ok(Err(Unsatisfiable::Weighted { weights, total })) =
+            find_minimum_presses_to_requirements(&config)
+        else {
+            panic!("expected the requirements to be proven unsatisfiable");
+        };
+        assert_eq!(weights, vec![-1, 1]);
+        assert_eq!(total, -2);
+    }
+
+    #[test]
+    fn failing_to_solve_a_machine_has_no_total() {
+        let summary = Summary {
+            solved: vec![(1, vec![0_usize, 2])],
+            unsatisfiable: vec![(2, Unsatisfiable::Exhausted)],
+            failed: vec![(3, "gave up".to_owned())],
+        };
+        assert!(summary.total(true).is_err());
+        assert!(summary.total(false).is_err());
+        let summary = Summary {
+            failed: vec![],
+            ..summary
+        };
+        assert_eq!(summary.total(true), Ok(2));
+        assert!(summary.total(false).is_err());
+    }
+
+    fn parse_error(line: &str) -> String {
+        match parse_configuration(line, 1) {
+            Ok(_) => panic!("{} should fail to parse", line),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn parses_multi_digit_numbers() {
+        let config = configuration("[...........#] (10,11) (0,1) {24,27,40,0,0,0,0,0,0,0,0,1}");
+        assert_eq!(config.light_count(), 12);
+        assert_eq!(config.target.ones(), vec![11]);
+        assert_eq!(config.buttons[0].ones(), vec![10, 11]);
+        assert_eq!(config.buttons[1].ones(), vec![0, 1]);
+        assert_eq!(
+            config.requirements,
+            vec![24, 27, 40, 0, 0, 0, 0, 0, 0, 0, 0, 1]
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        assert_eq!(
+            parse_error("[.#] (0,1 {1,1}"),
+            "column 11: expected Comma but found OpenBrace"
+        );
+        assert_eq!(
+            parse_error("[.#] (0,1) {1,1} (0)"),
+            "column 18: unexpected OpenParen after joltage requirements"
+        );
+        assert_eq!(
+            parse_error("[.#] (0,1) {1,1} x"),
+            "column 18: unexpected character 'x'"
+        );
+        assert_eq!(
+            parse_error("[.#] (0,1) {1,1"),
+            "column 16: expected Comma but reached the end of the line"
+        );
+        assert_eq!(
+            parse_error("[.# (0,1) {1,1}"),
+            "column 5: expected CloseBracket but found OpenParen"
+        );
+    }
+
+    #[test]
+    fn rejects_indices_and_requirements_not_matching_lights() {
+        assert_eq!(
+            parse_error("[.#] (0,2) {1,1}"),
+            "column 6: button index 2 is out of range for 2 lights"
+        );
+        assert_eq!(
+            parse_error("[.#] (0,1) {1,1,1}"),
+            "column 12: found 3 joltage requirements for 2 lights"
+        );
     }
 
-    fn set_equals(&self, set: &HashSet<u64>) -> bool {
-        self.set == *set
+    #[test]
+    fn example_input() {
+        let configs = parse_input("inputs/10test.txt").expect("example input should parse");
+        let lights = solve_each(&configs, find_minimum_button_presses_to_target);
+        assert_eq!(lights.total(false), Ok(7));
+        let joltage = solve_each(&configs, find_minimum_presses_to_requirements);
+        assert_eq!(joltage.total(false), Ok(33));
+        let presses: Vec<_> = joltage
+            .solved
+            .iter()
+            .map(|(_, p)| p.total_presses())
+            .collect();
+        assert_eq!(presses, vec![10, 12, 11]);
     }
 }
@@ -1,15 +1,138 @@
-use std::{collections::HashMap, fs};
+#[path = "../common/big_uint.rs"]
+mod big_uint;
+#[path = "../common/checked_number.rs"]
+mod checked_number;
+#[path = "../common/flags.rs"]
+mod flags;
+#[path = "../common/rng.rs"]
+mod rng;
+
+use big_uint::BigUint;
+use checked_number::CheckedNumber;
+use flags::{flag_number, flag_value};
+use rng::Rng;
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs,
+};
 
 const INPUT_PATH: &str = "inputs/11.txt";
 
 /// https://adventofcode.com/2025/day/11
+///
+/// A custom query can also be run with `--from <node> --to <node>`, optionally with
+/// `--via <node,node,...>` for nodes every path must visit, `--ordered` if they must be visited
+/// in the order given and `--avoid <node,node,...>` for nodes no path may visit.
+///
+/// Path counts fail rather than overflow a u64, pass `--big` to count with arbitrary precision.
+///
+/// Example paths from `you` to `out`, or between the nodes given by `--from` and `--to`, can be
+/// shown with `--list <limit>` for the first paths in lexicographic order, `--nth <index>` for the
+/// path at that index in the same order and `--sample <count>` for paths picked uniformly at
+/// random, seeded with `--seed <seed>`.
+///
+/// The graph can be read from another file with `--input <path>`, which may also be a Graphviz
+/// DOT digraph. `--dot <path>` writes the graph out as DOT, adding `--dot-counts` labels every node
+/// with its number of paths to `out`.
+///
+/// `--must-pass` reports the nodes and edges that every path from `svr` to `out`, or between the
+/// nodes given by `--from` and `--to`, goes through, along with the share of paths through every
+/// node and its dominators.
 fn main() {
-    let edges = match parse_input(INPUT_PATH) {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let input_path = match flag_value(&args, "--input") {
+        Ok(input_path) => input_path.unwrap_or(INPUT_PATH),
+        Err(e) => return println!("Failed with error: {}", e),
+    };
+    let (edges, name_id_map) = match parse_input(input_path) {
         Ok(input) => input,
         Err(e) => return println!("Failed with error: {}", e),
     };
-    println!("Answer 1 is {}", count_paths_start_to_end(&edges));
-    println!("Answer 2 is {}", count_paths_sr_to_end_via_fft_dac(&edges));
+    let graph = match Dag::new(edges, name_id_map) {
+        Ok(graph) => graph,
+        Err(e) => return println!("Failed with error: {}", e),
+    };
+    if let Err(e) = export_dot(&graph, &args) {
+        return println!("Failed with error: {}", e);
+    }
+    if args.iter().any(|arg| arg == "--big") {
+        print_answers::<BigUint>(&graph, &args);
+    } else {
+        print_answers::<u64>(&graph, &args);
+    }
+}
+
+fn print_answers<T: PathCount>(graph: &Dag, args: &[String]) {
+    match count_paths_start_to_end::<T>(graph) {
+        Ok(answer) => println!("Answer 1 is {}", answer),
+        Err(e) => println!("Failed with error: {}", e),
+    }
+    match count_paths_sr_to_end_via_fft_dac::<T>(graph) {
+        Ok(answer) => println!("Answer 2 is {}", answer),
+        Err(e) => println!("Failed with error: {}", e),
+    }
+
+    match parse_query(args, &graph.names) {
+        Ok(Some(query)) => match count_paths_for_query::<T>(graph, &query) {
+            Ok(answer) => println!("Query answer is {}", answer),
+            Err(e) => println!("Failed with error: {}", e),
+        },
+        Ok(None) => {}
+        Err(e) => println!("Failed to parse query with error: {}", e),
+    }
+    if let Err(e) = print_must_pass::<T>(graph, args) {
+        println!("Failed with error: {}", e);
+    }
+    if let Err(e) = print_example_paths::<T>(graph, args) {
+        println!("Failed with error: {}", e);
+    }
+}
+
+/// Print the must pass analysis if asked to on the command line.
+fn print_must_pass<T: PathCount>(graph: &Dag, args: &[String]) -> Result<(), String> {
+    if !args.iter().any(|arg| arg == "--must-pass") {
+        return Ok(());
+    }
+    let (from, to) = endpoints(
+        graph,
+        args,
+        (GraphNode::ServerRack, GraphNode::End),
+        "The must pass analysis",
+    )?;
+    let analysis = MustPass::<T>::new(graph, from, to)?;
+    println!(
+        "There are {} paths from {} to {}",
+        analysis.total,
+        graph.names.name(from),
+        graph.names.name(to)
+    );
+    println!("Must pass nodes: {}", graph.format_path(&analysis.nodes));
+    let edges: Vec<_> = analysis
+        .edges
+        .iter()
+        .map(|&(parent, child)| graph.format_path(&[parent, child]))
+        .collect();
+    if edges.is_empty() {
+        println!("Must pass edges: none");
+    } else {
+        println!("Must pass edges: {}", edges.join(", "));
+    }
+    for (node, paths) in &analysis.paths_through {
+        println!(
+            "{} is on {:.6} of paths, immediately dominated by {} and post dominated by {}",
+            graph.names.name(*node),
+            analysis.fraction(paths),
+            analysis
+                .dominators
+                .get(node)
+                .map_or("nothing", |&dominator| graph.names.name(dominator)),
+            analysis
+                .post_dominators
+                .get(node)
+                .map_or("nothing", |&dominator| graph.names.name(dominator))
+        );
+    }
+    Ok(())
 }
 
 /// Expecting a directed acyclic graph in format of a row containing a name of a node
@@ -21,8 +144,27 @@ fn main() {
 ///   - fft -> Fft
 ///
 /// The rest are short strings, since the value isn't something we care about we instead assign incrementing integer IDs to each to save on space and computation.
-fn parse_input(input_path: &str) -> Result<HashMap<GraphNode, Vec<GraphNode>>, String> {
+///
+/// Input starting with `digraph` or in a `.dot` or `.gv` file is read as a DOT edge list instead,
+/// see [`parse_dot`].
+fn parse_input(
+    input_path: &str,
+) -> Result<(HashMap<GraphNode, Vec<GraphNode>>, NameIdentifierMap), String> {
     let input_text = fs::read_to_string(input_path).map_err(|e| e.to_string())?;
+    let first_word = input_text.split_whitespace().next().unwrap_or("");
+    if input_path.ends_with(".dot")
+        || input_path.ends_with(".gv")
+        || ["digraph", "strict", "graph"].contains(&first_word.to_lowercase().as_str())
+    {
+        return parse_dot(&input_text);
+    }
+    parse_adjacency_list(&input_text)
+}
+
+/// Read the graph from lines of the form `name: child child ...`, as described on [`parse_input`].
+fn parse_adjacency_list(
+    input_text: &str,
+) -> Result<(HashMap<GraphNode, Vec<GraphNode>>, NameIdentifierMap), String> {
     let mut name_id_map = NameIdentifierMap::new();
     let mut graph = HashMap::new();
     for line in input_text.lines() {
@@ -39,7 +181,263 @@ fn parse_input(input_path: &str) -> Result<HashMap<GraphNode, Vec<GraphNode>>, S
             _ => return Err("Invalid input".to_owned()),
         }
     }
-    Ok(graph)
+    Ok((graph, name_id_map))
+}
+
+/// Read the edges from a Graphviz DOT digraph, such as `digraph { a -> b; b -> c -> out }`. Only
+/// node and edge statements are used, attributes are skipped over and subgraphs aren't supported.
+fn parse_dot(
+    input_text: &str,
+) -> Result<(HashMap<GraphNode, Vec<GraphNode>>, NameIdentifierMap), String> {
+    let tokens = tokenize_dot(input_text)?;
+    let mut parser = DotParser {
+        tokens: &tokens,
+        position: 0,
+    };
+    let mut name_id_map = NameIdentifierMap::new();
+    let mut graph: HashMap<GraphNode, Vec<GraphNode>> = HashMap::new();
+
+    if parser.peek_keyword("strict") {
+        parser.next();
+    }
+    if parser.peek_keyword("graph") {
+        return Err(format!(
+            "line {}: only directed graphs are supported",
+            parser.line()
+        ));
+    }
+    if !parser.peek_keyword("digraph") {
+        return Err(format!("line {}: expected digraph", parser.line()));
+    }
+    parser.next();
+    if let Some(DotToken::Id(_)) = parser.peek() {
+        parser.next();
+    }
+    parser.expect(&DotToken::Symbol('{'))?;
+    loop {
+        let line = parser.line();
+        match parser.next() {
+            Some(DotToken::Symbol('}')) => break,
+            Some(DotToken::Symbol(';')) => continue,
+            Some(DotToken::Id(id)) => {
+                let keyword = id.to_lowercase();
+                if ["graph", "node", "edge"].contains(&keyword.as_str())
+                    && parser.peek() == Some(&DotToken::Symbol('['))
+                {
+                    parser.skip_attributes()?;
+                } else if keyword == "subgraph" {
+                    return Err(format!("line {}: subgraphs aren't supported", line));
+                } else if parser.peek() == Some(&DotToken::Symbol('=')) {
+                    // A graph attribute such as `rankdir = LR`.
+                    parser.next();
+                    parser.expect_id()?;
+                } else {
+                    let mut from = parse_node(id, &mut name_id_map);
+                    graph.entry(from).or_default();
+                    while parser.peek() == Some(&DotToken::Arrow) {
+                        parser.next();
+                        let to = parse_node(parser.expect_id()?, &mut name_id_map);
+                        graph.entry(from).or_default().push(to);
+                        graph.entry(to).or_default();
+                        from = to;
+                    }
+                    if parser.peek() == Some(&DotToken::Symbol('[')) {
+                        parser.skip_attributes()?;
+                    }
+                }
+            }
+            Some(DotToken::UndirectedEdge) => {
+                return Err(format!("line {}: only directed edges are supported", line));
+            }
+            Some(DotToken::Symbol('{')) => {
+                return Err(format!("line {}: subgraphs aren't supported", line));
+            }
+            Some(token) => return Err(format!("line {}: unexpected {:?}", line, token)),
+            None => return Err("expected } but reached the end of the input".to_owned()),
+        }
+    }
+    if parser.peek().is_some() {
+        return Err(format!(
+            "line {}: unexpected input after the graph",
+            parser.line()
+        ));
+    }
+    Ok((graph, name_id_map))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum DotToken {
+    /// A name, number or quoted string.
+    Id(String),
+    Arrow,
+    UndirectedEdge,
+    Symbol(char),
+}
+
+/// Split DOT input into tokens along with the line they start on, dropping comments.
+fn tokenize_dot(input_text: &str) -> Result<Vec<(usize, DotToken)>, String> {
+    let mut tokens = vec![];
+    let mut chars = input_text.chars().peekable();
+    let mut line = 1;
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => {}
+            '#' => while chars.next_if(|&c| c != '\n').is_some() {},
+            '/' if chars.peek() == Some(&'/') => while chars.next_if(|&c| c != '\n').is_some() {},
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if previous == '*' => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            previous = c;
+                        }
+                        None => return Err(format!("line {}: unterminated comment", line)),
+                    }
+                }
+            }
+            '-' if chars.peek() == Some(&'>') => {
+                chars.next();
+                tokens.push((line, DotToken::Arrow));
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                chars.next();
+                tokens.push((line, DotToken::UndirectedEdge));
+            }
+            '"' => {
+                let start_line = line;
+                let mut id = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            id.push('"');
+                        }
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            id.push(c);
+                        }
+                        None => return Err(format!("line {}: unterminated string", start_line)),
+                    }
+                }
+                tokens.push((start_line, DotToken::Id(id)));
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' => {
+                let mut id = c.to_string();
+                while let Some(c) = chars.next_if(|&c| c.is_alphanumeric() || c == '_' || c == '.')
+                {
+                    id.push(c);
+                }
+                tokens.push((line, DotToken::Id(id)));
+            }
+            '{' | '}' | '[' | ']' | ';' | ',' | '=' => tokens.push((line, DotToken::Symbol(c))),
+            c => return Err(format!("line {}: unexpected character {:?}", line, c)),
+        }
+    }
+    Ok(tokens)
+}
+
+struct DotParser<'a> {
+    tokens: &'a [(usize, DotToken)],
+    position: usize,
+}
+
+impl<'a> DotParser<'a> {
+    fn peek(&self) -> Option<&'a DotToken> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(DotToken::Id(id)) if id.eq_ignore_ascii_case(keyword))
+    }
+
+    fn next(&mut self) -> Option<&'a DotToken> {
+        let token = self.peek();
+        self.position += 1;
+        token
+    }
+
+    // The line of the next token, or the last line if there are none left.
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .or(self.tokens.last())
+            .map_or(1, |&(line, _)| line)
+    }
+
+    fn expect(&mut self, expected: &DotToken) -> Result<(), String> {
+        let line = self.line();
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!(
+                "line {}: expected {:?} but found {:?}",
+                line, expected, token
+            )),
+            None => Err(format!(
+                "line {}: expected {:?} but reached the end of the input",
+                line, expected
+            )),
+        }
+    }
+
+    fn expect_id(&mut self) -> Result<&'a str, String> {
+        let line = self.line();
+        match self.next() {
+            Some(DotToken::Id(id)) => Ok(id),
+            Some(token) => Err(format!(
+                "line {}: expected a name but found {:?}",
+                line, token
+            )),
+            None => Err(format!(
+                "line {}: expected a name but reached the end of the input",
+                line
+            )),
+        }
+    }
+
+    // An attribute list such as `[shape=box, label="a"]`, which we have no use for.
+    fn skip_attributes(&mut self) -> Result<(), String> {
+        self.expect(&DotToken::Symbol('['))?;
+        loop {
+            let line = self.line();
+            match self.next() {
+                Some(DotToken::Symbol(']')) => return Ok(()),
+                Some(DotToken::Id(_) | DotToken::Symbol('=' | ',' | ';')) => {}
+                Some(token) => {
+                    return Err(format!(
+                        "line {}: unexpected {:?} in attributes",
+                        line, token
+                    ));
+                }
+                None => return Err(format!("line {}: unterminated attributes", line)),
+            }
+        }
+    }
+}
+
+/// Write the graph as a Graphviz DOT digraph if asked to on the command line.
+fn export_dot(graph: &Dag, args: &[String]) -> Result<(), String> {
+    let Some(dot_path) = flag_value(args, "--dot")? else {
+        return Ok(());
+    };
+    // Counted exactly, so that labelling never fails however many paths there are.
+    let paths_to_end_by_node = if args.iter().any(|arg| arg == "--dot-counts") {
+        Some(graph.count_paths_to::<BigUint>(GraphNode::End)?)
+    } else {
+        None
+    };
+    fs::write(dot_path, graph.to_dot(paths_to_end_by_node.as_ref()))
+        .map_err(|e| format!("Failed to write {}: {}", dot_path, e))?;
+    println!("Wrote graph to {}", dot_path);
+    Ok(())
 }
 
 /// Return a graph node for a string name. If it is a known keyword name we assign a special
@@ -57,31 +455,48 @@ fn parse_node(node_name: &str, name_id_map: &mut NameIdentifierMap) -> GraphNode
 
 struct NameIdentifierMap {
     node_name_to_id: HashMap<String, u64>,
-    next_id: u64,
+    // The name for each ID, indexed by ID.
+    id_to_node_name: Vec<String>,
 }
 
 impl NameIdentifierMap {
     fn new() -> Self {
         Self {
             node_name_to_id: HashMap::new(),
-            next_id: 0,
+            id_to_node_name: vec![],
         }
     }
 
+    /// The original name of a node in the input.
+    fn name(&self, node: GraphNode) -> &str {
+        match node {
+            GraphNode::Start => "you",
+            GraphNode::End => "out",
+            GraphNode::ServerRack => "svr",
+            GraphNode::Dac => "dac",
+            GraphNode::Fft => "fft",
+            GraphNode::Node(id) => &self.id_to_node_name[id as usize],
+        }
+    }
+
+    fn get_id(&self, name: &str) -> Option<u64> {
+        self.node_name_to_id.get(name).copied()
+    }
+
     fn register_and_get_id(&mut self, name: &str) -> u64 {
         match self.node_name_to_id.get(name) {
             Some(&existing_id) => existing_id,
             None => {
-                let id = self.next_id;
+                let id = self.id_to_node_name.len() as u64;
                 self.node_name_to_id.insert(name.to_string(), id);
-                self.next_id += 1;
+                self.id_to_node_name.push(name.to_string());
                 id
             }
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 enum GraphNode {
     Start,
     ServerRack,
@@ -91,49 +506,894 @@ enum GraphNode {
     End,
 }
 
-fn count_paths_start_to_end(graph: &HashMap<GraphNode, Vec<GraphNode>>) -> u64 {
-    let mut path_lengths = HashMap::new();
-    count_paths_dfs(graph, GraphNode::Start, GraphNode::End, &mut path_lengths)
+/// A directed acyclic graph, along with its nodes in topological order so that we can work through
+/// them without recursing.
+struct Dag {
+    edges: HashMap<GraphNode, Vec<GraphNode>>,
+    // Every node in the graph, ordered so that every edge goes from an earlier node to a later one.
+    order: Vec<GraphNode>,
+    names: NameIdentifierMap,
+}
+
+impl Dag {
+    /// Sort the graph topologically with a depth first search. If the graph has a cycle, the error
+    /// names the nodes on it.
+    fn new(
+        edges: HashMap<GraphNode, Vec<GraphNode>>,
+        names: NameIdentifierMap,
+    ) -> Result<Self, String> {
+        let mut nodes: Vec<GraphNode> = edges
+            .iter()
+            .flat_map(|(&from, to_nodes)| std::iter::once(from).chain(to_nodes.iter().copied()))
+            .collect();
+        nodes.sort();
+        nodes.dedup();
+
+        // Nodes that are on the current search path are false, and ones that are finished true.
+        let mut finished: HashMap<GraphNode, bool> = HashMap::new();
+        let mut post_order = vec![];
+        for root in nodes {
+            if finished.contains_key(&root) {
+                continue;
+            }
+            // The path from the root to where we are, with the index of the next child to visit.
+            let mut path = vec![(root, 0)];
+            finished.insert(root, false);
+            while let Some(&(node, next_child)) = path.last() {
+                let children = edges.get(&node).map_or(&[][..], Vec::as_slice);
+                let Some(&child) = children.get(next_child) else {
+                    finished.insert(node, true);
+                    post_order.push(node);
+                    path.pop();
+                    continue;
+                };
+                if let Some(last) = path.last_mut() {
+                    last.1 += 1;
+                }
+                match finished.get(&child) {
+                    None => {
+                        finished.insert(child, false);
+                        path.push((child, 0));
+                    }
+                    Some(false) => {
+                        let cycle_start = path.iter().position(|&(n, _)| n == child).unwrap_or(0);
+                        let cycle: Vec<_> = path[cycle_start..]
+                            .iter()
+                            .map(|&(n, _)| names.name(n))
+                            .chain(std::iter::once(names.name(child)))
+                            .collect();
+                        return Err(format!("graph has a cycle {}", cycle.join(" -> ")));
+                    }
+                    Some(true) => {}
+                }
+            }
+        }
+        post_order.reverse();
+        Ok(Self {
+            edges,
+            order: post_order,
+            names,
+        })
+    }
+
+    fn children(&self, node: GraphNode) -> &[GraphNode] {
+        self.edges.get(&node).map_or(&[], Vec::as_slice)
+    }
+
+    /// The number of paths from `start` to every node, worked out in a single pass forwards through
+    /// the topological order so that every parent is counted before its children.
+    fn count_paths_from<T: PathCount>(
+        &self,
+        start: GraphNode,
+    ) -> Result<HashMap<GraphNode, T>, String> {
+        let mut paths_from_start_by_node: HashMap<GraphNode, T> = HashMap::new();
+        paths_from_start_by_node.insert(start, T::one());
+        for &node in &self.order {
+            let Some(paths) = paths_from_start_by_node.get(&node).cloned() else {
+                continue;
+            };
+            for &child in self.children(node) {
+                let child_paths = paths_from_start_by_node
+                    .entry(child)
+                    .or_insert_with(T::zero);
+                *child_paths = child_paths
+                    .checked_add(&paths)
+                    .ok_or_else(|| self.overflow_error::<T>(child))?;
+            }
+        }
+        Ok(paths_from_start_by_node)
+    }
+
+    /// The number of paths from every node to `end`, worked out in a single pass backwards
+    /// through the topological order so that every child is counted before its parents.
+    fn count_paths_to<T: PathCount>(
+        &self,
+        end: GraphNode,
+    ) -> Result<HashMap<GraphNode, T>, String> {
+        let mut paths_to_end_by_node: HashMap<GraphNode, T> = HashMap::new();
+        for &node in self.order.iter().rev() {
+            let mut paths = if node == end { T::one() } else { T::zero() };
+            if node != end {
+                for child in self.children(node) {
+                    if let Some(child_paths) = paths_to_end_by_node.get(child) {
+                        paths = paths
+                            .checked_add(child_paths)
+                            .ok_or_else(|| self.overflow_error::<T>(node))?;
+                    }
+                }
+            }
+            paths_to_end_by_node.insert(node, paths);
+        }
+        Ok(paths_to_end_by_node)
+    }
+
+    /// The graph in Graphviz DOT format, with the special nodes picked out. If given the number of
+    /// paths to the end from each node, every node is labelled with it.
+    fn to_dot(&self, paths_to_end_by_node: Option<&HashMap<GraphNode, BigUint>>) -> String {
+        let mut dot = "digraph devices {\n".to_owned();
+        for &node in &self.order {
+            let mut attributes = vec![];
+            if let Some(paths_to_end_by_node) = paths_to_end_by_node {
+                let paths = paths_to_end_by_node
+                    .get(&node)
+                    .map_or("0".to_owned(), BigUint::to_string);
+                let label = format!("{}\\n{}", self.names.name(node), paths);
+                attributes.push(format!("label=\"{}\"", label.replace('"', "\\\"")));
+            }
+            let style = match node {
+                GraphNode::Start => Some("shape=invhouse, style=filled, fillcolor=palegreen"),
+                GraphNode::End => Some("shape=house, style=filled, fillcolor=lightcoral"),
+                GraphNode::ServerRack => Some("shape=box, style=filled, fillcolor=lightblue"),
+                GraphNode::Dac | GraphNode::Fft => {
+                    Some("shape=diamond, style=filled, fillcolor=gold")
+                }
+                GraphNode::Node(_) => None,
+            };
+            attributes.extend(style.map(str::to_owned));
+            // Every node is listed, so that ones without any edges aren't lost.
+            if attributes.is_empty() {
+                dot += &format!("    {};\n", self.dot_id(node));
+            } else {
+                dot += &format!("    {} [{}];\n", self.dot_id(node), attributes.join(", "));
+            }
+        }
+        for &node in &self.order {
+            for &child in self.children(node) {
+                dot += &format!("    {} -> {};\n", self.dot_id(node), self.dot_id(child));
+            }
+        }
+        dot + "}\n"
+    }
+
+    /// The name of a node as a quoted DOT ID.
+    fn dot_id(&self, node: GraphNode) -> String {
+        format!("\"{}\"", self.names.name(node).replace('"', "\\\""))
+    }
+
+    /// A path written out with the original node names.
+    fn format_path(&self, path: &[GraphNode]) -> String {
+        let names: Vec<_> = path.iter().map(|&node| self.names.name(node)).collect();
+        names.join(" -> ")
+    }
+
+    fn overflow_error<T: PathCount>(&self, node: GraphNode) -> String {
+        format!(
+            "number of paths through {} is too big for {}, try again with --big",
+            self.names.name(node),
+            T::NAME
+        )
+    }
+}
+
+fn count_paths_start_to_end<T: PathCount>(graph: &Dag) -> Result<T, String> {
+    Ok(graph
+        .count_paths_to::<T>(GraphNode::End)?
+        .remove(&GraphNode::Start)
+        .unwrap_or_else(T::zero))
+}
+
+fn count_paths_sr_to_end_via_fft_dac<T: PathCount>(graph: &Dag) -> Result<T, String> {
+    let query = PathQuery {
+        from: GraphNode::ServerRack,
+        to: GraphNode::End,
+        required: vec![GraphNode::Fft, GraphNode::Dac],
+        ordered: false,
+        forbidden: HashSet::new(),
+    };
+    count_paths_for_query(graph, &query)
+}
+
+/// Look up the graph node for a name given on the command line, without registering a new one.
+fn lookup_node(node_name: &str, name_id_map: &NameIdentifierMap) -> Result<GraphNode, String> {
+    match node_name {
+        "you" => Ok(GraphNode::Start),
+        "out" => Ok(GraphNode::End),
+        "svr" => Ok(GraphNode::ServerRack),
+        "dac" => Ok(GraphNode::Dac),
+        "fft" => Ok(GraphNode::Fft),
+        name => name_id_map
+            .get_id(name)
+            .map(GraphNode::Node)
+            .ok_or(format!("Unknown node: {}", name)),
+    }
+}
+
+/// Build a query from the command line arguments, returns None if no query was asked for.
+fn parse_query(
+    args: &[String],
+    name_id_map: &NameIdentifierMap,
+) -> Result<Option<PathQuery>, String> {
+    let flag_value = |flag: &str| flag_value(args, flag);
+    let node_list = |flag: &str| -> Result<Vec<GraphNode>, String> {
+        flag_value(flag)?
+            .map(|names| {
+                names
+                    .split(',')
+                    .map(|name| lookup_node(name, name_id_map))
+                    .collect()
+            })
+            .unwrap_or(Ok(vec![]))
+    };
+    let (from, to) = match (flag_value("--from")?, flag_value("--to")?) {
+        (Some(from), Some(to)) => (from, to),
+        (None, None) => return Ok(None),
+        _ => return Err("Both --from and --to are needed for a query".to_owned()),
+    };
+    Ok(Some(PathQuery {
+        from: lookup_node(from, name_id_map)?,
+        to: lookup_node(to, name_id_map)?,
+        required: node_list("--via")?,
+        ordered: args.iter().any(|arg| arg == "--ordered"),
+        forbidden: node_list("--avoid")?.into_iter().collect(),
+    }))
 }
 
-fn count_paths_sr_to_end_via_fft_dac(graph: &HashMap<GraphNode, Vec<GraphNode>>) -> u64 {
-    let mut path_lengths = HashMap::new();
-    let sr_to_fft = count_paths_dfs(
+/// Which paths through the graph to count.
+struct PathQuery {
+    from: GraphNode,
+    to: GraphNode,
+    /// Nodes that every path must visit.
+    required: Vec<GraphNode>,
+    /// Whether the required nodes must be visited in the order given, rather than any order.
+    ordered: bool,
+    /// Nodes that no path may visit.
+    forbidden: HashSet<GraphNode>,
+}
+
+/// Count the paths matching a query. Along with the node we are at we keep track of which of the
+/// required nodes we have visited so far as a bitmask. Working forwards through the topological
+/// order from `from`, we count the paths reaching each node for every combination of required
+/// nodes seen along the way.
+fn count_paths_for_query<T: PathCount>(graph: &Dag, query: &PathQuery) -> Result<T, String> {
+    if query.required.len() >= u64::BITS as usize {
+        return Err(format!("Too many required nodes: {}", query.required.len()));
+    }
+    // A path can only visit each node once, so we'd never see the second copy of a repeated one.
+    if let Some(repeated) =
+        (0..query.required.len()).find(|&i| query.required[..i].contains(&query.required[i]))
+    {
+        return Err(format!(
+            "Required node given more than once: {}",
+            graph.names.name(query.required[repeated])
+        ));
+    }
+    // Which required nodes have been visited after arriving at `node`, or None if arriving there
+    // means the path can't count.
+    let visit = |node: GraphNode, visited: u64| -> Option<u64> {
+        if query.forbidden.contains(&node) {
+            return None;
+        }
+        match query.required.iter().position(|&required| required == node) {
+            // Visiting a required node out of order means this path can't count.
+            Some(index) if query.ordered && visited != (1 << index) - 1 => None,
+            Some(index) => Some(visited | 1 << index),
+            None => Some(visited),
+        }
+    };
+    let Some(start_visited) = visit(query.from, 0) else {
+        return Ok(T::zero());
+    };
+    let mut paths_by_node: HashMap<GraphNode, HashMap<u64, T>> = HashMap::new();
+    paths_by_node.insert(query.from, HashMap::from([(start_visited, T::one())]));
+    let start = graph
+        .order
+        .iter()
+        .position(|&node| node == query.from)
+        .unwrap_or(graph.order.len());
+    for &node in &graph.order[start..] {
+        // Paths stop once they reach the end.
+        if node == query.to {
+            continue;
+        }
+        let Some(paths_by_visited) = paths_by_node.remove(&node) else {
+            continue;
+        };
+        for &child in graph.children(node) {
+            for (&visited, paths) in &paths_by_visited {
+                if let Some(child_visited) = visit(child, visited) {
+                    let child_paths = paths_by_node
+                        .entry(child)
+                        .or_default()
+                        .entry(child_visited)
+                        .or_insert_with(T::zero);
+                    *child_paths = child_paths
+                        .checked_add(paths)
+                        .ok_or_else(|| graph.overflow_error::<T>(child))?;
+                }
+            }
+        }
+    }
+    let all_visited = (1 << query.required.len()) - 1;
+    Ok(paths_by_node
+        .get_mut(&query.to)
+        .and_then(|paths_by_visited| paths_by_visited.remove(&all_visited))
+        .unwrap_or_else(T::zero))
+}
+
+/// The nodes given by `--from` and `--to`, or the defaults if there aren't any. `what` names the
+/// feature for the error when `--via` or `--avoid` are given, since it can't be limited by them.
+fn endpoints(
+    graph: &Dag,
+    args: &[String],
+    default: (GraphNode, GraphNode),
+    what: &str,
+) -> Result<(GraphNode, GraphNode), String> {
+    match parse_query(args, &graph.names)? {
+        Some(query) if query.required.is_empty() && query.forbidden.is_empty() => {
+            Ok((query.from, query.to))
+        }
+        Some(_) => Err(format!("{} can't be limited with --via or --avoid", what)),
+        None => Ok(default),
+    }
+}
+
+/// Print the example paths asked for on the command line, if any.
+fn print_example_paths<T: PathCount>(graph: &Dag, args: &[String]) -> Result<(), String> {
+    let list_limit = flag_number(args, "--list")?;
+    let index = flag_value(args, "--nth")?
+        .map(|value| {
+            T::from_str_radix(value, 10).map_err(|e| format!("Invalid value for --nth: {}", e))
+        })
+        .transpose()?;
+    let sample_count = flag_number(args, "--sample")?;
+    if list_limit.is_none() && index.is_none() && sample_count.is_none() {
+        return Ok(());
+    }
+    let (from, to) = endpoints(
         graph,
-        GraphNode::ServerRack,
-        GraphNode::Fft,
-        &mut path_lengths,
-    );
-    let mut path_lengths = HashMap::new();
-    let fft_to_dac = count_paths_dfs(graph, GraphNode::Fft, GraphNode::Dac, &mut path_lengths);
-    let mut path_lengths = HashMap::new();
-    let dac_to_end = count_paths_dfs(graph, GraphNode::Dac, GraphNode::End, &mut path_lengths);
-    sr_to_fft * fft_to_dac * dac_to_end
-}
-
-// Depth first search from start to end, maintaining a map of nodes and the number of paths from that node to the end.
-fn count_paths_dfs(
-    graph: &HashMap<GraphNode, Vec<GraphNode>>,
-    start: GraphNode,
-    end: GraphNode,
-    // The distance taken to get from the overall start to each node
-    paths_to_end_by_node: &mut HashMap<GraphNode, u64>,
-) -> u64 {
-    if start == end {
-        return 1;
-    }
-    // We have already seen this node before in a different path, return the known number of paths from here to the end without calculating
-    if let Some(&len) = paths_to_end_by_node.get(&start) {
-        return len;
-    }
-    let children = vec![];
-    let children = graph.get(&start).unwrap_or(&children);
-    let total_paths_to_end = children
+        args,
+        (GraphNode::Start, GraphNode::End),
+        "Example paths",
+    )?;
+    let paths = PathIndex::<T>::new(graph, from, to)?;
+    if let Some(limit) = list_limit {
+        for (index, path) in paths.first(limit).enumerate() {
+            println!("Path {} is {}", index, graph.format_path(&path));
+        }
+    }
+    if let Some(index) = index {
+        match paths.nth(index.clone()) {
+            Some(path) => println!("Path {} is {}", index, graph.format_path(&path)),
+            None => println!("There is no path {}, only {}", index, paths.len()),
+        }
+    }
+    if let Some(count) = sample_count {
+        let mut rng = Rng::new(flag_number(args, "--seed")?.unwrap_or(0));
+        for _ in 0..count {
+            let Some((index, path)) = paths.sample(&mut rng) else {
+                return Err(format!(
+                    "There are no paths from {} to {}",
+                    graph.names.name(from),
+                    graph.names.name(to)
+                ));
+            };
+            println!("Random path {} is {}", index, graph.format_path(&path));
+        }
+    }
+    Ok(())
+}
+
+/// Looks up paths between two nodes by their index when every path is sorted lexicographically by
+/// the names of its nodes. Knowing how many paths lead from each child to the end, we can skip
+/// over whole groups of paths at a time without walking them.
+struct PathIndex<'a, T> {
+    graph: &'a Dag,
+    from: GraphNode,
+    to: GraphNode,
+    paths_to_end_by_node: HashMap<GraphNode, T>,
+}
+
+impl<'a, T: PathCount> PathIndex<'a, T> {
+    fn new(graph: &'a Dag, from: GraphNode, to: GraphNode) -> Result<Self, String> {
+        Ok(Self {
+            graph,
+            from,
+            to,
+            paths_to_end_by_node: graph.count_paths_to(to)?,
+        })
+    }
+
+    fn paths_from(&self, node: GraphNode) -> T {
+        self.paths_to_end_by_node
+            .get(&node)
+            .cloned()
+            .unwrap_or_else(T::zero)
+    }
+
+    /// The total number of paths.
+    fn len(&self) -> T {
+        self.paths_from(self.from)
+    }
+
+    /// The path at `index`, or None if there aren't that many paths.
+    fn nth(&self, mut index: T) -> Option<Vec<GraphNode>> {
+        if index >= self.len() {
+            return None;
+        }
+        let mut path = vec![self.from];
+        let mut node = self.from;
+        while node != self.to {
+            let mut children = self.graph.children(node).to_vec();
+            children.sort_by_key(|&child| self.graph.names.name(child));
+            // Skip past every child whose paths all come before the one we want.
+            node = children.into_iter().find(|&child| {
+                let paths = self.paths_from(child);
+                if index < paths {
+                    return true;
+                }
+                // Can't fail, since the index is at least the number of paths here.
+                index = index.checked_sub(&paths).unwrap_or_else(T::zero);
+                false
+            })?;
+            path.push(node);
+        }
+        Some(path)
+    }
+
+    /// Up to `limit` paths, from the start of the order.
+    fn first(&self, limit: u64) -> impl Iterator<Item = Vec<GraphNode>> + '_ {
+        (0..limit).map_while(|index| self.nth(T::from_u64(index)))
+    }
+
+    /// A path picked uniformly at random along with its index, or None if there are no paths.
+    fn sample(&self, rng: &mut Rng) -> Option<(T, Vec<GraphNode>)> {
+        let index = self.len().random_below(rng)?;
+        let path = self.nth(index.clone())?;
+        Some((index, path))
+    }
+}
+
+/// The nodes and edges that every path between two nodes goes through. In a DAG a node dominates
+/// another if every path from the source to it passes through the node, so the must pass nodes
+/// are the dominators of the sink. We only look at the nodes on some path from the source to the
+/// sink, the rest can't affect the answer.
+struct MustPass<T> {
+    /// The number of paths from the source to the sink.
+    total: T,
+    /// The must pass nodes, in the order paths visit them, from the source to the sink.
+    nodes: Vec<GraphNode>,
+    /// The must pass edges, in the order paths visit them.
+    edges: Vec<(GraphNode, GraphNode)>,
+    /// The number of paths through every node on a path, in topological order.
+    paths_through: Vec<(GraphNode, T)>,
+    /// The immediate dominator of every node on a path apart from the source.
+    dominators: HashMap<GraphNode, GraphNode>,
+    /// The immediate post dominator of every node on a path apart from the sink, the first node
+    /// that every path from it to the sink passes through.
+    post_dominators: HashMap<GraphNode, GraphNode>,
+}
+
+impl<T: PathCount> MustPass<T> {
+    fn new(graph: &Dag, from: GraphNode, to: GraphNode) -> Result<Self, String> {
+        let paths_from_start = graph.count_paths_from::<T>(from)?;
+        let paths_to_end = graph.count_paths_to::<T>(to)?;
+        let zero = T::zero();
+        let count = |paths: &HashMap<GraphNode, T>, node: GraphNode| -> T {
+            paths.get(&node).cloned().unwrap_or_else(T::zero)
+        };
+        let total = count(&paths_from_start, to);
+        if total == zero {
+            return Err(format!(
+                "There are no paths from {} to {}",
+                graph.names.name(from),
+                graph.names.name(to)
+            ));
+        }
+
+        // A node is on a path if it can be reached from the source and can reach the sink. Every
+        // path through a node is a path to it followed by a path from it.
+        let mut order = vec![];
+        let mut paths_through = vec![];
+        for &node in &graph.order {
+            let paths = count(&paths_from_start, node)
+                .checked_mul(&count(&paths_to_end, node))
+                .ok_or_else(|| graph.overflow_error::<T>(node))?;
+            if paths != zero {
+                order.push(node);
+                paths_through.push((node, paths));
+            }
+        }
+        let on_path: HashSet<GraphNode> = order.iter().copied().collect();
+        let mut parents: HashMap<GraphNode, Vec<GraphNode>> = HashMap::new();
+        let mut children: HashMap<GraphNode, Vec<GraphNode>> = HashMap::new();
+        for &node in &order {
+            for &child in graph.children(node) {
+                if on_path.contains(&child) {
+                    parents.entry(child).or_default().push(node);
+                    children.entry(node).or_default().push(child);
+                }
+            }
+        }
+        let dominators = immediate_dominators(&order, &parents);
+        order.reverse();
+        let post_dominators = immediate_dominators(&order, &children);
+
+        let mut nodes = vec![to];
+        while let Some(&dominator) = dominators.get(nodes.last().unwrap_or(&to)) {
+            nodes.push(dominator);
+        }
+        nodes.reverse();
+
+        // An edge is must pass if every path uses it, so the paths to its start followed by the
+        // paths from its end are all of them.
+        let mut edges = vec![];
+        for pair in nodes.windows(2) {
+            let &[parent, child] = pair else {
+                continue;
+            };
+            let uses = graph
+                .children(parent)
+                .iter()
+                .filter(|&&node| node == child)
+                .count();
+            let paths = count(&paths_from_start, parent)
+                .checked_mul(&count(&paths_to_end, child))
+                .ok_or_else(|| graph.overflow_error::<T>(child))?;
+            if uses == 1 && paths == total {
+                edges.push((parent, child));
+            }
+        }
+
+        Ok(Self {
+            total,
+            nodes,
+            edges,
+            paths_through,
+            dominators,
+            post_dominators,
+        })
+    }
+
+    /// The fraction of all paths that some number of them make up.
+    fn fraction(&self, paths: &T) -> f64 {
+        paths.ratio_to(&self.total)
+    }
+}
+
+/// The immediate dominator of every node in `order` apart from the first, which is the root. Every
+/// node must come after all of its parents in the order, in which case the immediate dominator of
+/// a node is the closest common dominator of its parents.
+fn immediate_dominators(
+    order: &[GraphNode],
+    parents: &HashMap<GraphNode, Vec<GraphNode>>,
+) -> HashMap<GraphNode, GraphNode> {
+    let position: HashMap<GraphNode, usize> = order
         .iter()
-        .map(|child| count_paths_dfs(graph, *child, end, paths_to_end_by_node))
-        .sum();
-    paths_to_end_by_node
-        .entry(start)
-        .or_insert(total_paths_to_end);
-    total_paths_to_end
+        .enumerate()
+        .map(|(i, &node)| (node, i))
+        .collect();
+    let mut dominators: HashMap<GraphNode, GraphNode> = HashMap::new();
+    for &node in order.iter().skip(1) {
+        let mut node_parents = parents.get(&node).into_iter().flatten().copied();
+        let Some(mut dominator) = node_parents.next() else {
+            continue;
+        };
+        for mut other in node_parents {
+            // Walk up the dominator tree from whichever is further down until the two meet.
+            while dominator != other {
+                if position[&dominator] > position[&other] {
+                    dominator = dominators[&dominator];
+                } else {
+                    other = dominators[&other];
+                }
+            }
+        }
+        dominators.insert(node, dominator);
+    }
+    dominators
+}
+
+/// A number of paths, which is also used to index into the paths, so it can pick out any of them.
+trait PathCount: CheckedNumber {
+    /// This divided by `total`, as a floating point number for working out proportions.
+    fn ratio_to(&self, total: &Self) -> f64;
+    /// A number picked uniformly from zero up to but not including this one, or None if this is
+    /// zero.
+    fn random_below(&self, rng: &mut Rng) -> Option<Self>;
+}
+
+impl PathCount for u64 {
+    fn ratio_to(&self, total: &Self) -> f64 {
+        *self as f64 / *total as f64
+    }
+
+    fn random_below(&self, rng: &mut Rng) -> Option<Self> {
+        rng.below(*self)
+    }
+}
+
+impl PathCount for BigUint {
+    // Either number could be too big for a float on its own, so drop the bits of both that are
+    // too small to matter first.
+    fn ratio_to(&self, total: &Self) -> f64 {
+        let shift = total.bit_len().saturating_sub(u64::BITS as usize);
+        self.shift_right(shift).to_f64() / total.shift_right(shift).to_f64()
+    }
+
+    // Pick random bits up to the length of this number, trying again if that gives a number
+    // that is too big, which happens less than half the time.
+    fn random_below(&self, rng: &mut Rng) -> Option<Self> {
+        if self.is_zero() {
+            return None;
+        }
+        let bits = self.bit_len();
+        loop {
+            let mut digits: Vec<u32> = (0..bits.div_ceil(32))
+                .map(|_| rng.next_u64() as u32)
+                .collect();
+            if let Some(top) = digits.last_mut()
+                && !bits.is_multiple_of(32)
+            {
+                *top &= (1 << (bits % 32)) - 1;
+            }
+            let value = BigUint::from_digits(digits);
+            if value < *self {
+                return Some(value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(input_path: &str) -> Dag {
+        let (edges, names) = parse_input(input_path).expect("input should parse");
+        Dag::new(edges, names).expect("input should be a DAG")
+    }
+
+    fn args(args: &str) -> Vec<String> {
+        args.split_whitespace().map(str::to_owned).collect()
+    }
+
+    fn count_query(graph: &Dag, query_args: &str) -> Result<u64, String> {
+        let query = parse_query(&args(query_args), &graph.names)?.expect("query should be given");
+        count_paths_for_query(graph, &query)
+    }
+
+    #[test]
+    fn query_visiting_nodes_in_any_order() {
+        let graph = graph("inputs/11test2.txt");
+        assert_eq!(count_query(&graph, "--from you --to out"), Ok(2));
+        assert_eq!(count_query(&graph, "--from you --to out --via aaa"), Ok(1));
+        assert_eq!(
+            count_query(&graph, "--from you --to out --via ccc,aaa"),
+            Ok(1)
+        );
+        assert_eq!(
+            count_query(&graph, "--from you --to out --via aaa,bbb"),
+            Ok(0)
+        );
+    }
+
+    #[test]
+    fn query_visiting_nodes_in_order() {
+        let graph = graph("inputs/11test2.txt");
+        let ordered = "--from you --to out --ordered --via";
+        assert_eq!(count_query(&graph, &format!("{} aaa,ccc", ordered)), Ok(1));
+        assert_eq!(count_query(&graph, &format!("{} ccc,aaa", ordered)), Ok(0));
+    }
+
+    #[test]
+    fn query_avoiding_nodes() {
+        let graph = graph("inputs/11test2.txt");
+        assert_eq!(
+            count_query(&graph, "--from you --to out --avoid aaa"),
+            Ok(1)
+        );
+        assert_eq!(
+            count_query(&graph, "--from you --to out --avoid aaa,bbb"),
+            Ok(0)
+        );
+        assert_eq!(
+            count_query(&graph, "--from you --to out --via bbb --avoid aaa"),
+            Ok(1)
+        );
+    }
+
+    #[test]
+    fn query_rejects_repeated_required_nodes() {
+        let graph = graph("inputs/11test2.txt");
+        assert_eq!(
+            count_query(&graph, "--from you --to out --via aaa,aaa"),
+            Err("Required node given more than once: aaa".to_owned())
+        );
+    }
+
+    fn graph_from_text(input_text: &str) -> Result<Dag, String> {
+        let (edges, names) = parse_adjacency_list(input_text)?;
+        Dag::new(edges, names)
+    }
+
+    #[test]
+    fn cycle_is_named() {
+        let graph = graph_from_text("you: a\na: b\nb: c\nc: a out");
+        assert_eq!(
+            graph.err(),
+            Some("graph has a cycle a -> b -> c -> a".to_owned())
+        );
+    }
+
+    #[test]
+    fn deep_chain_counts_without_recursing() {
+        // Far deeper than the stack could take if we recursed once per node.
+        let depth = 100_000;
+        let mut input_text = "you: n0 out\n".to_owned();
+        for i in 0..depth {
+            input_text += &format!("n{}: n{}\n", i, i + 1);
+        }
+        input_text += &format!("n{}: out\n", depth);
+        let graph = graph_from_text(&input_text).expect("chain should be a DAG");
+        assert_eq!(count_paths_start_to_end::<u64>(&graph), Ok(2));
+    }
+
+    const EXAMPLE_PATHS: [&str; 5] = [
+        "you -> bbb -> ddd -> ggg -> out",
+        "you -> bbb -> eee -> out",
+        "you -> ccc -> ddd -> ggg -> out",
+        "you -> ccc -> eee -> out",
+        "you -> ccc -> fff -> out",
+    ];
+
+    #[test]
+    fn paths_listed_in_lexicographic_order() {
+        let graph = graph("inputs/11test.txt");
+        let paths = PathIndex::<u64>::new(&graph, GraphNode::Start, GraphNode::End)
+            .expect("paths should count");
+        assert_eq!(paths.len(), 5);
+        let listed: Vec<_> = paths
+            .first(10)
+            .map(|path| graph.format_path(&path))
+            .collect();
+        assert_eq!(listed, EXAMPLE_PATHS);
+        assert_eq!(paths.first(2).count(), 2);
+    }
+
+    #[test]
+    fn nth_path() {
+        let graph = graph("inputs/11test.txt");
+        let paths = PathIndex::<u64>::new(&graph, GraphNode::Start, GraphNode::End)
+            .expect("paths should count");
+        for (index, expected) in EXAMPLE_PATHS.iter().enumerate() {
+            let path = paths.nth(index as u64).expect("index is in range");
+            assert_eq!(graph.format_path(&path), *expected);
+        }
+        assert_eq!(paths.nth(paths.len()), None);
+        let big_paths = PathIndex::<BigUint>::new(&graph, GraphNode::Start, GraphNode::End)
+            .expect("paths should count");
+        let path = big_paths
+            .nth(BigUint::from_u64(3))
+            .expect("index is in range");
+        assert_eq!(graph.format_path(&path), EXAMPLE_PATHS[3]);
+        assert_eq!(big_paths.nth(big_paths.len()), None);
+    }
+
+    #[test]
+    fn sampled_paths_match_their_index() {
+        let graph = graph("inputs/11test.txt");
+        let paths = PathIndex::<u64>::new(&graph, GraphNode::Start, GraphNode::End)
+            .expect("paths should count");
+        let mut rng = Rng::new(0);
+        let mut seen = [false; 5];
+        for _ in 0..100 {
+            let (index, path) = paths.sample(&mut rng).expect("there are paths");
+            assert_eq!(graph.format_path(&path), EXAMPLE_PATHS[index as usize]);
+            seen[index as usize] = true;
+        }
+        assert_eq!(seen, [true; 5]);
+        let no_paths = PathIndex::<u64>::new(&graph, GraphNode::End, GraphNode::Start)
+            .expect("paths should count");
+        assert_eq!(no_paths.sample(&mut rng), None);
+    }
+
+    // Every edge by the names of the nodes at either end, sorted so graphs can be compared.
+    fn edge_names(
+        (edges, names): &(HashMap<GraphNode, Vec<GraphNode>>, NameIdentifierMap),
+    ) -> Vec<(String, String)> {
+        let mut edge_names: Vec<_> = edges
+            .iter()
+            .flat_map(|(&from, to_nodes)| {
+                to_nodes
+                    .iter()
+                    .map(move |&to| (names.name(from).to_owned(), names.name(to).to_owned()))
+            })
+            .collect();
+        edge_names.sort();
+        edge_names
+    }
+
+    fn edges(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|&(from, to)| (from.to_owned(), to.to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn dot_round_trip() {
+        let input = parse_input("inputs/11test.txt").expect("input should parse");
+        let expected = edge_names(&input);
+        let graph = Dag::new(input.0, input.1).expect("input should be a DAG");
+        let counts = graph
+            .count_paths_to::<BigUint>(GraphNode::End)
+            .expect("BigUint can't overflow");
+        for dot in [graph.to_dot(None), graph.to_dot(Some(&counts))] {
+            let parsed = parse_dot(&dot).expect("exported DOT should parse");
+            assert_eq!(edge_names(&parsed), expected);
+            let graph = Dag::new(parsed.0, parsed.1).expect("exported DOT should be a DAG");
+            assert_eq!(count_paths_start_to_end::<u64>(&graph), Ok(5));
+        }
+    }
+
+    #[test]
+    fn dot_comments_are_skipped() {
+        let dot =
+            "// A comment\ndigraph {\n  a -> b; # another\n  /* and one\n  -> c */ b -> out\n}\n";
+        let parsed = parse_dot(dot).expect("DOT should parse");
+        assert_eq!(edge_names(&parsed), edges(&[("a", "b"), ("b", "out")]));
+        assert!(parse_dot("digraph { a -> b /* never closed }").is_err());
+    }
+
+    #[test]
+    fn dot_quoted_ids() {
+        let dot = r#"digraph "devices" { "my node" -> "say \"hi\"" -> "out" }"#;
+        let parsed = parse_dot(dot).expect("DOT should parse");
+        assert_eq!(
+            edge_names(&parsed),
+            edges(&[("my node", "say \"hi\""), ("say \"hi\"", "out")])
+        );
+        assert!(parse_dot(r#"digraph { "a -> b }"#).is_err());
+    }
+
+    #[test]
+    fn dot_attributes_are_skipped() {
+        let dot = r#"strict digraph g {
+            graph [rankdir=LR];
+            node [shape=box, style=filled];
+            rankdir = LR;
+            a [label="A"];
+            a -> b -> c [color=red; weight=2]
+            c -> out;
+        }"#;
+        let parsed = parse_dot(dot).expect("DOT should parse");
+        assert_eq!(
+            edge_names(&parsed),
+            edges(&[("a", "b"), ("b", "c"), ("c", "out")])
+        );
+        assert!(parse_dot("digraph { a [label=\"A\" -> b }").is_err());
+    }
+
+    #[test]
+    fn dot_rejects_undirected_graphs_and_subgraphs() {
+        for dot in [
+            "graph { a -- b }",
+            "digraph { a -- b }",
+            "digraph { subgraph cluster { a -> b } }",
+            "digraph { { a b } -> c }",
+            "digraph { a -> b } c",
+        ] {
+            assert!(parse_dot(dot).is_err(), "{} should be rejected", dot);
+        }
+    }
 }
@@ -1,86 +1,790 @@
-use std::fs;
+#[path = "../common/big_uint.rs"]
+mod big_uint;
+#[path = "../common/checked_number.rs"]
+mod checked_number;
+#[path = "../common/flags.rs"]
+mod flags;
+#[path = "../common/table.rs"]
+mod table;
+
+use big_uint::BigUint;
+use checked_number::{CheckedNumber, ParseNumberError};
+use flags::{flag_number, flag_value};
+use std::{env, fmt, fs};
+use table::{Align, print_table};
 
 const INPUT_PATH: &str = "inputs/2.txt";
+const DEFAULT_RADIX: u32 = 10;
 
 /// https://adventofcode.com/2025/day/2
+///
+/// IDs can be written in any base from 2 to 36 with `--radix <base>`, in which case the answers
+/// are given in that base as well as in decimal.
+///
+/// IDs and sums are worked out as u128 and fail rather than overflow, pass `--digits` to allow IDs
+/// with any number of digits.
+///
+/// A custom rule can also be run with `--repeats <count>`, `--repeats <min>..`,
+/// `--repeats <min>..=<max>` or `--repeats ..=<max>` for how many times the pattern must repeat,
+/// optionally with `--min-pattern-len <digits>` for the shortest pattern allowed. `--report` also
+/// prints how many invalid IDs each rule found.
+///
+/// `--ranges <table|json>` breaks the invalid IDs down by the range they came from, adding `--ids`
+/// lists the IDs themselves.
 fn main() {
-    let input = match parse_input(INPUT_PATH) {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let radix = match parse_radix(&args) {
+        Ok(radix) => radix,
+        Err(e) => return println!("Failed with error: {}", e),
+    };
+    let custom_rule = match parse_custom_rule(&args) {
+        Ok(custom_rule) => custom_rule,
+        Err(e) => return println!("Failed with error: {}", e),
+    };
+    let mut rules = vec![
+        ("Answer 1", RepetitionRule::exactly(2)),
+        ("Answer 2", RepetitionRule::at_least(2)),
+    ];
+    rules.extend(custom_rule.map(|rule| ("Custom answer", rule)));
+    let options = Options {
+        radix,
+        report: args.iter().any(|arg| arg == "--report"),
+        range_format: match flag_value(&args, "--ranges") {
+            Ok(None) => None,
+            Ok(Some("table")) => Some(RangeFormat::Table),
+            Ok(Some("json")) => Some(RangeFormat::Json),
+            _ => return println!("Failed with error: --ranges must be table or json"),
+        },
+        list_ids: args.iter().any(|arg| arg == "--ids"),
+    };
+    if args.iter().any(|arg| arg == "--digits") {
+        print_answers::<BigUint>(&options, &rules);
+    } else {
+        print_answers::<u128>(&options, &rules);
+    }
+}
+
+/// How to show the answers.
+struct Options {
+    radix: u32,
+    /// Whether to report how many invalid IDs each rule found.
+    report: bool,
+    /// How to break the invalid IDs down by range, if at all.
+    range_format: Option<RangeFormat>,
+    /// Whether to list every invalid ID in the range breakdown.
+    list_ids: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RangeFormat {
+    Table,
+    Json,
+}
+
+fn print_answers<T: CheckedNumber>(options: &Options, rules: &[(&str, RepetitionRule)]) {
+    let radix = options.radix;
+    let input = match parse_input::<T>(INPUT_PATH, radix) {
         Ok(input) => input,
         Err(e) => return println!("Failed with error: {}", e),
     };
+    if options.range_format == Some(RangeFormat::Json) {
+        return match print_range_json(&input, options, rules) {
+            Ok(()) => {}
+            Err(e) => println!("Failed with error: {}", e),
+        };
+    }
+    for warning in &input.warnings {
+        println!("Warning: {}", warning);
+    }
+    let mut totals_by_rule = vec![];
+    for (name, rule) in rules {
+        match invalid_id_totals_in_ranges(&input.ranges, radix, rule) {
+            Ok(totals) => {
+                println!("{} is: {}", name, format_answer(&totals.sum, radix));
+                totals_by_rule.push((rule, totals));
+            }
+            Err(e) => println!("Failed with error: {}", e),
+        }
+    }
+    if options.report {
+        for (rule, totals) in totals_by_rule {
+            println!(
+                "{} invalid IDs are {}, adding up to {}",
+                totals.count,
+                rule,
+                format_answer(&totals.sum, radix)
+            );
+        }
+    }
+    if options.range_format == Some(RangeFormat::Table) {
+        for (_, rule) in rules {
+            if let Err(e) = print_range_table(&input, options, rule) {
+                println!("Failed with error: {}", e);
+            }
+        }
+    }
+}
+
+/// Print a table of the invalid IDs found by a rule in every range.
+fn print_range_table<T: CheckedNumber>(
+    input: &Input<T>,
+    options: &Options,
+    rule: &RepetitionRule,
+) -> Result<(), String> {
+    let mut header = vec!["#", "range", "invalid IDs", "sum"];
+    if options.list_ids {
+        header.push("IDs");
+    }
+    let mut rows = vec![];
+    for (range, position) in input.ranges.iter().zip(&input.positions) {
+        let totals = invalid_id_totals_in_range(range, options.radix, rule)?;
+        let mut row = vec![
+            position.to_string(),
+            format_range(range, options.radix),
+            totals.count.to_string(),
+            format_answer(&totals.sum, options.radix),
+        ];
+        if options.list_ids {
+            let ids: Vec<_> = invalid_ids_in_range(range, options.radix, rule)?
+                .iter()
+                .map(|id| id.to_string_radix(options.radix))
+                .collect();
+            row.push(ids.join(" "));
+        }
+        rows.push(row);
+    }
+    println!("Invalid IDs {}:", rule);
+    // Everything but the IDs is lined up on the right.
+    let mut align = vec![Align::Right; 4];
+    align.push(Align::Left);
+    print_table(&header, &rows, &align);
+    Ok(())
+}
+
+/// Print every rule's answer broken down by range as JSON, along with any warnings about the input.
+/// Counts and sums are decimal numbers, while ranges and IDs are strings in the input's base so
+/// that they can be matched up with it.
+fn print_range_json<T: CheckedNumber>(
+    input: &Input<T>,
+    options: &Options,
+    rules: &[(&str, RepetitionRule)],
+) -> Result<(), String> {
+    let radix = options.radix;
+    let warnings: Vec<_> = input.warnings.iter().map(|w| json_string(w)).collect();
+    let mut rule_objects = vec![];
+    for (name, rule) in rules {
+        let totals = invalid_id_totals_in_ranges(&input.ranges, radix, rule)?;
+        let mut range_objects = vec![];
+        for (range, position) in input.ranges.iter().zip(&input.positions) {
+            let range_totals = invalid_id_totals_in_range(range, radix, rule)?;
+            let mut fields = vec![
+                format!("\"position\": {}", position),
+                format!("\"range\": {}", json_string(&format_range(range, radix))),
+                format!("\"count\": {}", range_totals.count),
+                format!("\"sum\": {}", range_totals.sum),
+            ];
+            if options.list_ids {
+                let ids: Vec<_> = invalid_ids_in_range(range, radix, rule)?
+                    .iter()
+                    .map(|id| json_string(&id.to_string_radix(radix)))
+                    .collect();
+                fields.push(format!("\"ids\": [{}]", ids.join(", ")));
+            }
+            range_objects.push(format!("        {{{}}}", fields.join(", ")));
+        }
+        rule_objects.push(format!(
+            "    {{\n      \"name\": {},\n      \"rule\": {},\n      \"count\": {},\n      \"sum\": {},\n      \"ranges\": [\n{}\n      ]\n    }}",
+            json_string(name),
+            json_string(&rule.to_string()),
+            totals.count,
+            totals.sum,
+            range_objects.join(",\n")
+        ));
+    }
     println!(
-        "Answer 1 is: {}",
-        sum_invalid_ids_in_ranges(&input, &id_is_double_sequence)
-    );
-    println!(
-        "Answer 2 is: {}",
-        sum_invalid_ids_in_ranges(&input, &id_has_repeated_digit_sequence)
+        "{{\n  \"radix\": {},\n  \"warnings\": [{}],\n  \"rules\": [\n{}\n  ]\n}}",
+        radix,
+        warnings.join(", "),
+        rule_objects.join(",\n")
     );
+    Ok(())
+}
+
+/// A string quoted and escaped for JSON.
+fn json_string(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn format_range<T: CheckedNumber>(range: &(T, T), radix: u32) -> String {
+    format!(
+        "{}-{}",
+        range.0.to_string_radix(radix),
+        range.1.to_string_radix(radix)
+    )
+}
+
+/// The base given by `--radix`, or decimal if there isn't one.
+fn parse_radix(args: &[String]) -> Result<u32, String> {
+    let Some(radix) = flag_number(args, "--radix")? else {
+        return Ok(DEFAULT_RADIX);
+    };
+    if !(2..=36).contains(&radix) {
+        return Err(format!("Radix must be from 2 to 36, got {}", radix));
+    }
+    Ok(radix)
+}
+
+/// The rule given by `--repeats` and `--min-pattern-len`, if any.
+fn parse_custom_rule(args: &[String]) -> Result<Option<RepetitionRule>, String> {
+    let repeats = flag_value(args, "--repeats")?;
+    let min_pattern_len = flag_number(args, "--min-pattern-len")?;
+    if repeats.is_none() && min_pattern_len.is_none() {
+        return Ok(None);
+    }
+    let mut rule = match repeats {
+        Some(repeats) => RepetitionRule::parse(repeats)?,
+        None => RepetitionRule::at_least(2),
+    };
+    if let Some(min_pattern_len) = min_pattern_len {
+        rule.min_pattern_len = min_pattern_len;
+    }
+    Ok(Some(rule))
+}
+
+/// An answer in decimal, followed by the chosen base if it isn't decimal.
+fn format_answer<T: CheckedNumber>(answer: &T, radix: u32) -> String {
+    if radix == 10 {
+        return answer.to_string();
+    }
+    format!(
+        "{} ({} in base {})",
+        answer,
+        answer.to_string_radix(radix),
+        radix
+    )
 }
 
 /// Input is a list of comma separated ranges in the format \d+-\d+
 /// e.g. 11-22,95-115,998-1012,1188511880-1188511890
-fn parse_input(input_path: &str) -> Result<Vec<(u64, u64)>, String> {
+///
+/// The IDs are written in base `radix`, e.g. ff-1a0 in hexadecimal.
+///
+/// Ranges that can't be read are skipped with a warning, and ranges that overlap are warned about
+/// since the IDs they share are counted twice. Ranges are numbered by where they are in the input,
+/// counting from 1.
+fn parse_input<T: CheckedNumber>(input_path: &str, radix: u32) -> Result<Input<T>, String> {
     let input_text = fs::read_to_string(input_path).map_err(|e| e.to_string())?;
-    input_text
-        .split(',')
-        .map(|range| {
-            let mut ids = range.split('-');
-            if let (Some(id1), Some(id2), None) = (ids.next(), ids.next(), ids.next()) {
-                return Ok((id1, id2));
+    let mut input = Input {
+        ranges: vec![],
+        positions: vec![],
+        warnings: vec![],
+    };
+    for (i, range) in input_text.trim().split(',').enumerate() {
+        let range = range.trim();
+        let parse_id = |id: &str| {
+            T::from_str_radix(id, radix).map_err(|e| match e {
+                ParseNumberError::Invalid(e) => {
+                    ParseNumberError::Invalid(format!("{:?} is not a valid ID ({})", id, e))
+                }
+                ParseNumberError::TooBig => ParseNumberError::TooBig,
+            })
+        };
+        let parsed = match range.split('-').collect::<Vec<_>>().as_slice() {
+            [id1_str, id2_str] => parse_id(id1_str).and_then(|id1| Ok((id1, parse_id(id2_str)?))),
+            _ => Err(ParseNumberError::Invalid(
+                "it doesn't have exactly two IDs".to_owned(),
+            )),
+        };
+        match parsed {
+            Ok(parsed) => {
+                if parsed.0 > parsed.1 {
+                    input.warnings.push(format!(
+                        "range {} ({}) ends before it starts, so has no IDs",
+                        i + 1,
+                        range
+                    ));
+                }
+                input.ranges.push(parsed);
+                input.positions.push(i + 1);
             }
-            Err("Invalid number of IDs found in range")
-        })
-        .map(|result| {
-            result.map(|(id1_str, id2_str)| {
-                let id1 = id1_str.parse::<u64>().map_err(|e| e.to_string())?;
-                let id2 = id2_str.parse::<u64>().map_err(|e| e.to_string())?;
-                Ok((id1, id2))
-            })?
-        })
-        .collect()
-}
-
-/// An ID is considered to be invalid if it is the same digits repeated twice.
-/// e.g. 11, 123123, 44004400
-fn id_is_double_sequence(id: &u64) -> bool {
-    // there's probably a nice mathsy way to do this??
-    let id_str = id.to_string();
-    let (left, right) = id_str.split_at(id_str.len() / 2);
-    left == right
-}
-
-/// An ID can also be considered to be invalid if it is the same digits repeated any number of times.
-/// e.g. 111, 121212, 111111, 456456
-fn id_has_repeated_digit_sequence(id: &u64) -> bool {
-    let id_str = id.to_string();
-    let id_len = id_str.len();
-    if id_len == 1 {
-        return false;
-    }
-    for sub_sequence_len in 1..id_len {
-        // Cannot be a repeated sequence if the length is not divisible by the sequence size.
-        if !id_len.is_multiple_of(sub_sequence_len) {
-            continue;
-        }
-        let mut chunks = id_str.as_bytes().chunks(sub_sequence_len);
-        let first = chunks.next();
-        if let Some(first) = first
-            && chunks.all(|c| c == first)
+            Err(ParseNumberError::Invalid(e)) => input.warnings.push(format!(
+                "skipped range {} ({:?}) because {}",
+                i + 1,
+                range,
+                e
+            )),
+            // Skipping this would quietly give the wrong answer, so it is worth failing over.
+            Err(ParseNumberError::TooBig) => {
+                return Err(format!("range {}: {}", i + 1, overflow_error::<T>()));
+            }
+        }
+    }
+
+    // Sort the ranges by where they start, then any range starting before the furthest end
+    // seen so far overlaps with the range that got there.
+    let mut order: Vec<usize> = (0..input.ranges.len())
+        .filter(|&i| input.ranges[i].0 <= input.ranges[i].1)
+        .collect();
+    order.sort_by(|&a, &b| input.ranges[a].0.cmp(&input.ranges[b].0));
+    let mut furthest: Option<usize> = None;
+    for i in order {
+        if let Some(furthest) = furthest
+            && input.ranges[i].0 <= input.ranges[furthest].1
         {
-            return true;
+            let (first, second) = (furthest.min(i), furthest.max(i));
+            input.warnings.push(format!(
+                "ranges {} ({}) and {} ({}) overlap, so the IDs in both are counted twice",
+                input.positions[first],
+                format_range(&input.ranges[first], radix),
+                input.positions[second],
+                format_range(&input.ranges[second], radix)
+            ));
+        }
+        if furthest.is_none_or(|furthest| input.ranges[i].1 > input.ranges[furthest].1) {
+            furthest = Some(i);
+        }
+    }
+    Ok(input)
+}
+
+/// The ranges read from the input, along with anything worth warning about in it.
+struct Input<T> {
+    ranges: Vec<(T, T)>,
+    /// Where each range was in the input, counting from 1.
+    positions: Vec<usize>,
+    warnings: Vec<String>,
+}
+
+/// Which IDs are invalid, those made of some pattern of digits repeated a number of times.
+/// e.g. the puzzle's first rule is a pattern repeated exactly twice, such as 11, 123123 and
+/// 44004400, while its second allows any number of repeats, such as 111, 121212 and 456456.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct RepetitionRule {
+    /// The fewest times the pattern can be repeated, at least 2.
+    min_repeats: u32,
+    /// The most times the pattern can be repeated, or None for no limit.
+    max_repeats: Option<u32>,
+    /// The fewest digits the pattern can have.
+    min_pattern_len: u32,
+}
+
+impl RepetitionRule {
+    fn exactly(repeats: u32) -> Self {
+        Self {
+            min_repeats: repeats,
+            max_repeats: Some(repeats),
+            min_pattern_len: 1,
+        }
+    }
+
+    fn at_least(repeats: u32) -> Self {
+        Self {
+            min_repeats: repeats,
+            max_repeats: None,
+            min_pattern_len: 1,
+        }
+    }
+
+    /// Read a number of repeats written as `3`, `3..`, `3..=5` or `..=5`.
+    fn parse(repeats: &str) -> Result<Self, String> {
+        let number = |value: &str| -> Result<u32, String> {
+            value
+                .parse()
+                .map_err(|e| format!("Invalid number of repeats {}: {}", value, e))
+        };
+        let (min_repeats, max_repeats) = match repeats.split_once("..") {
+            None => (number(repeats)?, Some(number(repeats)?)),
+            Some((min, max)) => {
+                let min_repeats = if min.is_empty() { 2 } else { number(min)? };
+                let max_repeats = match max.strip_prefix('=') {
+                    Some(max) => Some(number(max)?),
+                    None if max.is_empty() => None,
+                    None => return Err(format!("Expected ..= in {}", repeats)),
+                };
+                (min_repeats, max_repeats)
+            }
+        };
+        if min_repeats < 2 {
+            return Err(format!(
+                "Patterns must repeat at least twice, got {}",
+                repeats
+            ));
+        }
+        if max_repeats.is_some_and(|max_repeats| max_repeats < min_repeats) {
+            return Err(format!("No number of repeats fits {}", repeats));
+        }
+        Ok(Self {
+            min_repeats,
+            max_repeats,
+            min_pattern_len: 1,
+        })
+    }
+
+    /// The lengths of pattern that can be repeated to make an invalid ID with `id_len` digits.
+    fn pattern_lengths(&self, id_len: u32) -> Vec<u32> {
+        (self.min_pattern_len.max(1)..id_len)
+            .filter(|&pattern_len| id_len.is_multiple_of(pattern_len))
+            .filter(|&pattern_len| {
+                let repeats = id_len / pattern_len;
+                repeats >= self.min_repeats && self.max_repeats.is_none_or(|max| repeats <= max)
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for RepetitionRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.max_repeats {
+            Some(max) if max == self.min_repeats => write!(f, "repeated {} times", max)?,
+            Some(max) => write!(f, "repeated {} to {} times", self.min_repeats, max)?,
+            None => write!(f, "repeated at least {} times", self.min_repeats)?,
+        }
+        if self.min_pattern_len > 1 {
+            write!(
+                f,
+                " with patterns of {} or more digits",
+                self.min_pattern_len
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// How many invalid IDs there are and what they add up to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct IdTotals<T> {
+    count: T,
+    sum: T,
+}
+
+impl<T: CheckedNumber> IdTotals<T> {
+    fn zero() -> Self {
+        Self {
+            count: T::zero(),
+            sum: T::zero(),
         }
     }
-    false
+
+    fn checked_add(&self, other: &Self) -> Result<Self, String> {
+        Ok(Self {
+            count: self
+                .count
+                .checked_add(&other.count)
+                .ok_or_else(overflow_error::<T>)?,
+            sum: self
+                .sum
+                .checked_add(&other.sum)
+                .ok_or_else(overflow_error::<T>)?,
+        })
+    }
+
+    fn checked_sub(&self, other: &Self) -> Result<Self, String> {
+        let error = || "IDs with a shorter pattern were counted twice".to_owned();
+        Ok(Self {
+            count: self.count.checked_sub(&other.count).ok_or_else(error)?,
+            sum: self.sum.checked_sub(&other.sum).ok_or_else(error)?,
+        })
+    }
+}
+
+fn overflow_error<T: CheckedNumber>() -> String {
+    format!("IDs are too big for {}, try again with --digits", T::NAME)
 }
 
-fn invalid_ids_in_range(range: &(u64, u64), is_invalid: &dyn Fn(&u64) -> bool) -> Vec<u64> {
-    (range.0..=range.1).filter(is_invalid).collect()
+/// `radix` to the power of `exponent`, or None if it is too big to represent.
+fn checked_pow<T: CheckedNumber>(radix: u32, exponent: u32) -> Option<T> {
+    let radix = T::from_u64(radix as u64);
+    (0..exponent).try_fold(T::one(), |power, _| power.checked_mul(&radix))
 }
 
-fn sum_invalid_ids_in_ranges(ranges: &[(u64, u64)], is_invalid: &dyn Fn(&u64) -> bool) -> u64 {
-    ranges
+/// The patterns of `pattern_len` digits which, repeated to fill `id_len` digits of base `radix`,
+/// make an ID in the range. Every such ID is the pattern multiplied by the repunit 1010...01, with
+/// a one for each repeat, so rather than checking every ID we work out the smallest and largest
+/// patterns that land in the range. Returns None if there aren't any.
+fn pattern_bounds<T: CheckedNumber>(
+    range: &(T, T),
+    radix: u32,
+    id_len: u32,
+    pattern_len: u32,
+) -> Result<Option<PatternBounds<T>>, String> {
+    let zero = T::zero();
+    let one = T::one();
+    // Every power below the length of the longest ID in the range is no bigger than it, so these
+    // never actually overflow.
+    let smallest_id = checked_pow::<T>(radix, id_len - 1).ok_or_else(overflow_error::<T>)?;
+    let pattern_base = checked_pow::<T>(radix, pattern_len).ok_or_else(overflow_error::<T>)?;
+    let mut repunit = zero.clone();
+    for _ in 0..id_len / pattern_len {
+        repunit = repunit
+            .checked_mul(&pattern_base)
+            .and_then(|repunit| repunit.checked_add(&one))
+            .ok_or_else(overflow_error::<T>)?;
+    }
+
+    // A pattern can't start with a zero, and the ID must land in the range. If even the next
+    // power is too big to represent, it is beyond the end of the range anyway.
+    let low = (&range.0).max(&smallest_id);
+    let high = match checked_pow::<T>(radix, id_len).and_then(|power| power.checked_sub(&one)) {
+        Some(largest_id) => (&range.1).min(&largest_id).clone(),
+        None => range.1.clone(),
+    };
+    let (first_pattern, remainder) = low.div_rem(&repunit).ok_or_else(overflow_error::<T>)?;
+    let first_pattern = if remainder == zero {
+        first_pattern
+    } else {
+        first_pattern
+            .checked_add(&one)
+            .ok_or_else(overflow_error::<T>)?
+    };
+    let (last_pattern, _) = high.div_rem(&repunit).ok_or_else(overflow_error::<T>)?;
+    if first_pattern > last_pattern {
+        return Ok(None);
+    }
+    Ok(Some(PatternBounds {
+        first_pattern,
+        last_pattern,
+        repunit,
+    }))
+}
+
+struct PatternBounds<T> {
+    first_pattern: T,
+    last_pattern: T,
+    repunit: T,
+}
+
+/// Every ID in the range made of a pattern of `pattern_len` digits repeated to fill `id_len`
+/// digits of base `radix`, all counted and summed at once from the bounds on the patterns.
+fn repeated_pattern_id_totals<T: CheckedNumber>(
+    range: &(T, T),
+    radix: u32,
+    id_len: u32,
+    pattern_len: u32,
+) -> Result<IdTotals<T>, String> {
+    let Some(PatternBounds {
+        first_pattern,
+        last_pattern,
+        repunit,
+    }) = pattern_bounds(range, radix, id_len, pattern_len)?
+    else {
+        return Ok(IdTotals::zero());
+    };
+    let zero = T::zero();
+    let one = T::one();
+
+    // The sum of the patterns is (first + last) * count / 2, halving whichever is even first so
+    // that nothing bigger than the answer is ever needed.
+    let two = T::from_u64(2);
+    let count = last_pattern
+        .checked_sub(&first_pattern)
+        .and_then(|difference| difference.checked_add(&one))
+        .ok_or_else(overflow_error::<T>)?;
+    let ends = first_pattern
+        .checked_add(&last_pattern)
+        .ok_or_else(overflow_error::<T>)?;
+    let pattern_sum = match count.div_rem(&two) {
+        Some((half_count, remainder)) if remainder == zero => ends.checked_mul(&half_count),
+        _ => ends
+            .div_rem(&two)
+            .and_then(|(half_ends, _)| half_ends.checked_mul(&count)),
+    };
+    let sum = pattern_sum
+        .and_then(|pattern_sum| pattern_sum.checked_mul(&repunit))
+        .ok_or_else(overflow_error::<T>)?;
+    Ok(IdTotals { count, sum })
+}
+
+/// Every invalid ID in the range with `id_len` digits.
+///
+/// An ID made of a repeated pattern is counted once for every pattern length that works, e.g.
+/// 111111 repeats 1, 11 and 111. To count each ID once we work out, for every possible length,
+/// the IDs whose shortest repeating pattern is exactly that long, by taking away the IDs with
+/// shorter patterns that divide it. An ID is then invalid if its shortest pattern length divides
+/// any of the allowed pattern lengths.
+fn invalid_id_totals_of_length<T: CheckedNumber>(
+    range: &(T, T),
+    radix: u32,
+    id_len: u32,
+    pattern_lengths: &[u32],
+) -> Result<IdTotals<T>, String> {
+    let divisors: Vec<u32> = (1..id_len)
+        .filter(|&len| id_len.is_multiple_of(len))
+        .collect();
+    let mut shortest_pattern_totals: Vec<(u32, IdTotals<T>)> = vec![];
+    for &len in &divisors {
+        let mut totals = repeated_pattern_id_totals(range, radix, id_len, len)?;
+        for (shorter, shorter_totals) in &shortest_pattern_totals {
+            if len.is_multiple_of(*shorter) {
+                totals = totals.checked_sub(shorter_totals)?;
+            }
+        }
+        shortest_pattern_totals.push((len, totals));
+    }
+    shortest_pattern_totals
         .iter()
-        .flat_map(|range| invalid_ids_in_range(range, is_invalid))
-        .sum()
+        .filter(|(shortest, _)| {
+            pattern_lengths
+                .iter()
+                .any(|&len| len.is_multiple_of(*shortest))
+        })
+        .try_fold(IdTotals::zero(), |total, (_, totals)| {
+            total.checked_add(totals)
+        })
+}
+
+fn invalid_id_totals_in_range<T: CheckedNumber>(
+    range: &(T, T),
+    radix: u32,
+    rule: &RepetitionRule,
+) -> Result<IdTotals<T>, String> {
+    if range.0 > range.1 {
+        return Ok(IdTotals::zero());
+    }
+    (range.0.digit_count(radix)..=range.1.digit_count(radix)).try_fold(
+        IdTotals::zero(),
+        |total, id_len| {
+            let totals =
+                invalid_id_totals_of_length(range, radix, id_len, &rule.pattern_lengths(id_len))?;
+            total.checked_add(&totals)
+        },
+    )
+}
+
+fn invalid_id_totals_in_ranges<T: CheckedNumber>(
+    ranges: &[(T, T)],
+    radix: u32,
+    rule: &RepetitionRule,
+) -> Result<IdTotals<T>, String> {
+    ranges.iter().try_fold(IdTotals::zero(), |total, range| {
+        total.checked_add(&invalid_id_totals_in_range(range, radix, rule)?)
+    })
+}
+
+/// Every invalid ID in the range, in order. Unlike the totals this has to go through the IDs one
+/// at a time, so can take a while for big ranges.
+fn invalid_ids_in_range<T: CheckedNumber>(
+    range: &(T, T),
+    radix: u32,
+    rule: &RepetitionRule,
+) -> Result<Vec<T>, String> {
+    let mut ids = vec![];
+    if range.0 > range.1 {
+        return Ok(ids);
+    }
+    let one = T::one();
+    for id_len in range.0.digit_count(radix)..=range.1.digit_count(radix) {
+        for pattern_len in rule.pattern_lengths(id_len) {
+            let Some(bounds) = pattern_bounds(range, radix, id_len, pattern_len)? else {
+                continue;
+            };
+            let mut pattern = bounds.first_pattern;
+            while pattern <= bounds.last_pattern {
+                ids.push(
+                    pattern
+                        .checked_mul(&bounds.repunit)
+                        .ok_or_else(overflow_error::<T>)?,
+                );
+                pattern = pattern.checked_add(&one).ok_or_else(overflow_error::<T>)?;
+            }
+        }
+    }
+    // An ID can repeat more than one allowed pattern length, e.g. 1111 repeats 1 and 11.
+    ids.sort();
+    ids.dedup();
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Whether an ID is invalid, worked out from its digits.
+    fn is_invalid(id: u128, radix: u32, rule: &RepetitionRule) -> bool {
+        let digits = id.to_string_radix(radix);
+        rule.pattern_lengths(digits.len() as u32)
+            .into_iter()
+            .any(|pattern_len| {
+                let pattern = &digits[..pattern_len as usize];
+                digits == pattern.repeat(digits.len() / pattern_len as usize)
+            })
+    }
+
+    fn brute_force_totals(
+        range: (u128, u128),
+        radix: u32,
+        rule: &RepetitionRule,
+    ) -> IdTotals<u128> {
+        let invalid: Vec<_> = (range.0..=range.1)
+            .filter(|&id| is_invalid(id, radix, rule))
+            .collect();
+        IdTotals {
+            count: invalid.len() as u128,
+            sum: invalid.iter().sum(),
+        }
+    }
+
+    #[test]
+    fn totals_match_brute_force() {
+        let rules = [
+            RepetitionRule::exactly(2),
+            RepetitionRule::at_least(2),
+            RepetitionRule::exactly(3),
+            RepetitionRule::parse("2..=3").expect("rule should parse"),
+            RepetitionRule {
+                min_pattern_len: 2,
+                ..RepetitionRule::at_least(2)
+            },
+        ];
+        let ranges_by_radix = [
+            (2, vec![(1, 5000), (37, 64), (1000, 1000)]),
+            (3, vec![(1, 5000), (80, 250)]),
+            (
+                10,
+                vec![(1, 30000), (95, 115), (998, 1012), (1188511880, 1188511890)],
+            ),
+            (16, vec![(1, 70000), (0xfff0, 0x10010)]),
+        ];
+        for (radix, ranges) in ranges_by_radix {
+            for range in ranges {
+                for rule in &rules {
+                    let expected = brute_force_totals(range, radix, rule);
+                    assert_eq!(
+                        invalid_id_totals_in_range(&range, radix, rule),
+                        Ok(expected.clone()),
+                        "range {:?} in base {} {}",
+                        range,
+                        radix,
+                        rule
+                    );
+                    let big_range = (
+                        BigUint::from_u64(range.0 as u64),
+                        BigUint::from_u64(range.1 as u64),
+                    );
+                    let big_totals = invalid_id_totals_in_range(&big_range, radix, rule)
+                        .expect("BigUint can't overflow");
+                    assert_eq!(big_totals.count.to_u64(), Some(expected.count as u64));
+                    assert_eq!(big_totals.sum.to_u64(), Some(expected.sum as u64));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn backwards_range_has_no_invalid_ids() {
+        let rule = RepetitionRule::at_least(2);
+        assert_eq!(
+            invalid_id_totals_in_range(&(100_u128, 10), 10, &rule),
+            Ok(IdTotals::zero())
+        );
+    }
 }
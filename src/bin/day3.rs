@@ -1,20 +1,60 @@
-use std::fs;
+use std::{env, fmt, fs};
 
 const INPUT_PATH: &str = "inputs/3.txt";
 
 /// https://adventofcode.com/2025/day/3
+///
+/// Pass `--batteries K` to also turn on K batteries per bank, and `--indices` to show which
+/// batteries were chosen in each bank.
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let custom_count = match parse_battery_count(&args) {
+        Ok(custom_count) => custom_count,
+        Err(e) => return println!("Failed with error: {}", e),
+    };
     let input = match parse_input(INPUT_PATH) {
         Ok(input) => input,
         Err(e) => return println!("Failed with error: {}", e),
     };
-    println!("Answer 1 is: {}", sum_highest_2_digit_joltages(&input));
-    println!("Answer 2 is: {}", sum_highest_12_digit_joltages(&input));
+    let mut answers = vec![("Answer 1", 2), ("Answer 2", 12)];
+    answers.extend(custom_count.map(|count| ("Custom answer", count)));
+    let show_indices = args.iter().any(|arg| arg == "--indices");
+    for (name, count) in answers {
+        let selections = match select_highest_joltages(&input, count) {
+            Ok(selections) => selections,
+            Err(e) => return println!("Failed with error: {}", e),
+        };
+        println!("{} is: {}", name, sum_joltages(&selections));
+        if show_indices {
+            for (i, selection) in selections.iter().enumerate() {
+                let indices: Vec<String> =
+                    selection.indices.iter().map(|i| i.to_string()).collect();
+                println!(
+                    "  bank {}: {} from batteries {}",
+                    i + 1,
+                    selection.joltage,
+                    indices.join(",")
+                );
+            }
+        }
+    }
+}
+
+/// The number of batteries given by `--batteries`, if any.
+fn parse_battery_count(args: &[String]) -> Result<Option<usize>, String> {
+    let Some(i) = args.iter().position(|arg| arg == "--batteries") else {
+        return Ok(None);
+    };
+    args.get(i + 1)
+        .ok_or("Missing value for --batteries")?
+        .parse()
+        .map(Some)
+        .map_err(|e| format!("Invalid value for --batteries: {}", e))
 }
 
 /// The input is a series of lines, each being a list of digits.
 /// We want to convert this into a list of list of numbers.
-fn parse_input(input_path: &str) -> Result<Vec<Vec<u64>>, String> {
+fn parse_input(input_path: &str) -> Result<Vec<Vec<u8>>, String> {
     let input_text = fs::read_to_string(input_path).map_err(|e| e.to_string())?;
     input_text
         .lines()
@@ -22,7 +62,7 @@ fn parse_input(input_path: &str) -> Result<Vec<Vec<u64>>, String> {
             l.chars()
                 .map(|c| {
                     char::to_digit(c, 10)
-                        .map(|d| d as u64)
+                        .map(|d| d as u8)
                         .ok_or(format!("failed to convert char {c} to digit"))
                 })
                 .collect()
@@ -30,93 +70,122 @@ fn parse_input(input_path: &str) -> Result<Vec<Vec<u64>>, String> {
         .collect()
 }
 
-fn get_non_last_index_of_largest_battery(battery_bank: &[u64]) -> usize {
-    let mut highest = 0;
-    let mut index_of_highest = 0;
-    battery_bank
-        .iter()
-        .enumerate()
-        .take(battery_bank.len() - 1)
-        .for_each(|(index, &battery)| {
-            if battery > highest {
-                index_of_highest = index;
-                highest = battery;
-            }
-        });
-    index_of_highest
+/// The batteries turned on in a bank and the joltage they produce.
+struct Selection {
+    /// Where the chosen batteries are in the bank, in order.
+    indices: Vec<usize>,
+    /// The chosen digits "concatenated".
+    joltage: Joltage,
 }
 
-fn get_highest_2_digit_joltage(battery_bank: &[u64]) -> u64 {
-    let first_battery_index = get_non_last_index_of_largest_battery(battery_bank);
-    let first_battery = battery_bank[first_battery_index];
-    let mut second_battery = 1;
-    battery_bank
-        .iter()
-        .skip(first_battery_index + 1)
-        .for_each(|&battery| {
-            if battery > second_battery {
-                second_battery = battery;
-            }
-        });
-    // "concatenate" the two digits
-    first_battery * 10 + second_battery
+/// A joltage of any size, kept as its decimal digits so that turning on hundreds of batteries
+/// doesn't overflow.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Joltage {
+    /// Least significant digit first, with no leading zeros, so zero has no digits.
+    digits: Vec<u8>,
 }
 
-fn sum_highest_2_digit_joltages(battery_banks: &[Vec<u64>]) -> u64 {
-    battery_banks
-        .iter()
-        .map(|bank| get_highest_2_digit_joltage(bank))
-        .sum()
-}
+impl Joltage {
+    /// "concatenates" the digits, most significant first.
+    fn from_digits(digits: impl DoubleEndedIterator<Item = u8>) -> Self {
+        let mut joltage = Self {
+            digits: digits.rev().collect(),
+        };
+        joltage.trim();
+        joltage
+    }
 
-fn get_highest_battery_index_in_range(
-    battery_bank: &[u64],
-    from_start: usize,
-    from_end: usize,
-) -> usize {
-    let mut highest = 0;
-    let mut index_of_highest = 0;
-    battery_bank
-        .iter()
-        .enumerate()
-        .take(battery_bank.len() - from_end)
-        .skip(from_start)
-        .for_each(|(index, &battery)| {
-            if battery > highest {
-                index_of_highest = index;
-                highest = battery;
+    fn add_assign(&mut self, other: &Self) {
+        if self.digits.len() < other.digits.len() {
+            self.digits.resize(other.digits.len(), 0);
+        }
+        let mut carry = 0;
+        for (i, digit) in self.digits.iter_mut().enumerate() {
+            let total = *digit + other.digits.get(i).copied().unwrap_or(0) + carry;
+            *digit = total % 10;
+            carry = total / 10;
+            if carry == 0 && i >= other.digits.len() {
+                break;
             }
-        });
-    index_of_highest
+        }
+        if carry > 0 {
+            self.digits.push(carry);
+        }
+    }
+
+    fn trim(&mut self) {
+        while self.digits.last() == Some(&0) {
+            self.digits.pop();
+        }
+    }
 }
 
-fn get_highest_12_digit_joltage(battery_bank: &[u64]) -> u64 {
-    let mut remaining: u32 = 11;
-    let mut previous_battery_index = get_highest_battery_index_in_range(
-        battery_bank,
-        0,
-        // We always want there to be enough digits left at the end to choose the rest of the batteries
-        remaining as usize,
-    );
-    let mut total: u64 = battery_bank[previous_battery_index] * 10_u64.pow(remaining);
-    while remaining > 0 {
-        remaining -= 1;
-        let next_battery_index = get_highest_battery_index_in_range(
-            battery_bank,
-            previous_battery_index + 1,
-            // We always want there to be enough digits left at the end to choose the rest of the batteries
-            remaining as usize,
-        );
-        total += battery_bank[next_battery_index] * 10_u64.pow(remaining);
-        previous_battery_index = next_battery_index;
+impl fmt::Display for Joltage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.digits.is_empty() {
+            return write!(f, "0");
+        }
+        let digits: String = self
+            .digits
+            .iter()
+            .rev()
+            .map(|&d| char::from(b'0' + d))
+            .collect();
+        f.pad(&digits)
     }
+}
 
-    total
+/// Turns on `count` batteries in the bank to get the highest joltage, which is the
+/// lexicographically largest subsequence of that length.
+///
+/// This keeps a stack of the batteries chosen so far. Each battery knocks any smaller batteries
+/// off the top of the stack, as long as enough batteries have been skipped to still leave
+/// `count`, so each battery is pushed and popped at most once.
+fn select_highest_joltage(battery_bank: &[u8], count: usize) -> Result<Selection, String> {
+    let mut skips_left = battery_bank.len().checked_sub(count).ok_or(format!(
+        "can't turn on {} batteries in a bank of {}",
+        count,
+        battery_bank.len()
+    ))?;
+    let mut indices: Vec<usize> = Vec::with_capacity(count);
+    for (index, &battery) in battery_bank.iter().enumerate() {
+        while skips_left > 0
+            && indices
+                .last()
+                .is_some_and(|&top| battery_bank[top] < battery)
+        {
+            indices.pop();
+            skips_left -= 1;
+        }
+        if indices.len() < count {
+            indices.push(index);
+        } else {
+            skips_left -= 1;
+        }
+    }
+    let joltage = Joltage::from_digits(indices.iter().map(|&index| battery_bank[index]));
+    Ok(Selection { indices, joltage })
 }
 
-fn sum_highest_12_digit_joltages(battery_banks: &[Vec<u64>]) -> u64 {
+fn select_highest_joltages(
+    battery_banks: &[Vec<u8>],
+    count: usize,
+) -> Result<Vec<Selection>, String> {
     battery_banks
         .iter()
-        .map(|bank| get_highest_12_digit_joltage(bank))
-        .sum()
+        .enumerate()
+        .map(|(i, bank)| {
+            select_highest_joltage(bank, count).map_err(|e| format!("bank {}: {}", i + 1, e))
+        })
+        .collect()
+}
+
+fn sum_joltages(selections: &[Selection]) -> Joltage {
+    selections
+        .iter()
+        .fold(Joltage::default(), |mut total, selection| {
+            total.add_assign(&selection.joltage);
+            total
+        })
 }
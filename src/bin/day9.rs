@@ -1,15 +1,30 @@
-use std::{collections::HashSet, fs};
+use std::{env, fs};
 
 const INPUT_PATH: &str = "inputs/9.txt";
 
 /// https://adventofcode.com/2025/day/9
+///
+/// Pass `--general` to allow the polygon to have diagonal edges. This fits rectangles as continuous
+/// shapes rather than by their tiles, so can give a smaller answer, see [`GeneralPolygon`].
 fn main() {
+    let general = env::args().skip(1).any(|arg| arg == "--general");
     let coordinates = match parse_input(INPUT_PATH) {
         Ok(input) => input,
         Err(e) => return println!("Failed with error: {}", e),
     };
     // Expected: 4763040296
     println!("Answer 1 is: {}", get_largest_rect_area(&coordinates));
+    if general {
+        let dance_floor = match GeneralPolygon::new(&coordinates) {
+            Ok(dance_floor) => dance_floor,
+            Err(e) => return println!("Failed to make dance floor with error: {}", e),
+        };
+        println!(
+            "Answer 2 is: {}",
+            get_largest_rect_area_entirely_in_general_polygon(&coordinates, &dance_floor)
+        );
+        return;
+    }
     let dance_floor = match Polygon::new(&coordinates) {
         Ok(dance_floor) => dance_floor,
         Err(e) => return println!("Failed to make dance floor with error: {}", e),
@@ -18,7 +33,7 @@ fn main() {
     // Expected: 1396494456
     println!(
         "Answer 2 is: {}",
-        get_largest_rect_area_entirely_in_polygon(&coordinates, &dance_floor)
+        get_largest_rect_area_entirely_in_polygon(&dance_floor)
     );
 }
 
@@ -53,65 +68,121 @@ fn get_largest_rect_area(coordinates: &[(usize, usize)]) -> usize {
     largest_area
 }
 
-fn get_largest_rect_area_entirely_in_polygon(
-    coordinates: &[(usize, usize)],
-    polygon: &Polygon,
-) -> usize {
-    let mut coord_pairs: Vec<((usize, usize), (usize, usize))> = vec![];
-    for i in 0..coordinates.len() - 1 {
-        for j in i..coordinates.len() {
-            let coord1 = coordinates[i];
-            let coord2 = coordinates[j];
-            coord_pairs.push((coord1, coord2));
+/// Rather than sorting every pair of coordinates by area up front, we visit each corner along with
+/// each quadrant it could extend into, in order of the largest rectangle that could fit there. We
+/// stop as soon as no remaining corner can beat the best valid rectangle found so far.
+///
+/// This is still quadratic in the worst case, since a corner may have to look at every vertex
+/// within its reach, but each corner stops looking once the outline around it rules out beating
+/// the best so far.
+fn get_largest_rect_area_entirely_in_polygon(polygon: &Polygon) -> usize {
+    let mut candidates = vec![];
+    for i in 0..polygon.vertices.len() {
+        for k in 0..4 {
+            let (width, height) = polygon.quadrant_reach(i, k);
+            candidates.push(((width + 1) * (height + 1), i, k, width, height));
         }
     }
-    // Sort the coordinates by rectangle area descending first, since we want to find
-    // the largest valid rectangle, this will reduce the number that we need to check.
-    coord_pairs.sort_by(|a, b| {
-        let rect_a = Rect::new(a.0, a.1);
-        let rect_b = Rect::new(b.0, b.1);
-        rect_b.area().cmp(&rect_a.area())
-    });
-    for &pair in coord_pairs.iter() {
-        if polygon.rect_entirely_inside(pair) {
-            return Rect::new(pair.0, pair.1).area();
+    candidates.sort_by(|a, b| b.cmp(a));
+
+    let mut largest_area = 0;
+    for (upper_bound, i, k, width, height) in candidates {
+        if upper_bound <= largest_area {
+            break;
         }
+        largest_area = polygon.largest_rect_area_from_corner(i, k, (width, height), largest_area);
     }
-    0
+    largest_area
 }
 
+/// For a polygon with diagonal edges we don't have anything clever to narrow down the search, so
+/// check every pair of coordinates from the largest area down until one fits.
+fn get_largest_rect_area_entirely_in_general_polygon(
+    coordinates: &[(usize, usize)],
+    polygon: &GeneralPolygon,
+) -> usize {
+    let mut rects = vec![];
+    for i in 0..coordinates.len() {
+        for j in i + 1..coordinates.len() {
+            rects.push(Rect::new(coordinates[i], coordinates[j]));
+        }
+    }
+    rects.sort_by_key(|rect| std::cmp::Reverse(rect.area()));
+    rects
+        .iter()
+        .find(|rect| polygon.contains_rect(rect))
+        .map_or(0, Rect::area)
+}
+
+/// A rectilinear polygon made from a closed loop of coordinates, where every edge is either
+/// horizontal or vertical. Each coordinate is a tile, and the polygon covers every tile on or
+/// within the loop.
+///
+/// Counting tiles rather than area matters where two edges run side by side. A gap one tile wide
+/// between them has no tiles in it, so a rectangle can cross it even though it leaves the area
+/// inside the loop. To handle this we grow the polygon by half a tile in every direction, which
+/// closes up any such gaps, and a rectangle of tiles then fits as long as it lies within the grown
+/// polygon. Everything about the grown polygon is kept in doubled coordinates so it stays on the
+/// grid, with the tile at (x, y) at (2x + 1, 2y + 1) and the outline of the grown polygon on even
+/// coordinates.
 struct Polygon {
-    sorted_vertical_edges: Vec<((usize, usize), (usize, usize))>,
-    rev_sorted_vertical_edges: Vec<((usize, usize), (usize, usize))>,
-    boundary_coords: HashSet<(usize, usize)>,
+    vertices: Vec<(usize, usize)>,
+    // For each vertex, how many tiles we can travel in each axis direction before leaving the
+    // polygon.
+    reach: Vec<[usize; 4]>,
+    // Vertices sorted by x then y, along with their original index.
+    vertices_by_x: Vec<(usize, usize, usize)>,
+    // The outline of the grown polygon, which may include holes.
+    outline_vertical_edges: EdgeTree,
+    outline_horizontal_edges: EdgeTree,
+    // Ends of the outline edges, sorted by x then y.
+    outline_vertices: Vec<(usize, usize)>,
 }
 
 impl Polygon {
     fn new(coordinates: &[(usize, usize)]) -> Result<Self, String> {
-        let mut boundary_coords = HashSet::new();
-        let mut sorted_vertical_edges = vec![];
+        if coordinates.len() < 4 {
+            return Err(format!(
+                "Need at least 4 coordinates to make a polygon, got {}",
+                coordinates.len()
+            ));
+        }
+        // Shoelace formula, we only care about the sign to know which way round the loop goes.
+        let twice_signed_area: i128 = (0..coordinates.len())
+            .map(|i| {
+                let (x0, y0) = coordinates[i];
+                let (x1, y1) = coordinates[(i + 1) % coordinates.len()];
+                x0 as i128 * y1 as i128 - x1 as i128 * y0 as i128
+            })
+            .sum();
+        let counter_clockwise = twice_signed_area > 0;
 
+        // Growing the polygon by half a tile is the same as covering the polygon along with a
+        // rectangle around each edge reaching half a tile past it on every side.
+        let mut edge_rects = vec![];
+        let mut vertical_edges = vec![];
+        let mut horizontal_edges = vec![];
         for i in 0..coordinates.len() {
-            let coords1 = coordinates[i];
-            let coords2 = if i == coordinates.len() - 1 {
-                coordinates[0]
+            let (x0, y0) = doubled(coordinates[i]);
+            let (x1, y1) = doubled(coordinates[(i + 1) % coordinates.len()]);
+            // Walking counter clockwise the inside is always on the left, so this tells us whether
+            // crossing the edge heading towards +x or +y takes us into the polygon.
+            let entering = if x0 == x1 {
+                (y1 < y0) == counter_clockwise
             } else {
-                coordinates[i + 1]
+                (x1 > x0) == counter_clockwise
             };
-            boundary_coords.insert(coords1);
-            let (x0, y0) = coords1;
-            let (x1, y1) = coords2;
-            if x0 == x1 {
-                let range = if y0 <= y1 { y0..=y1 } else { y1..=y0 };
-                for y in range {
-                    boundary_coords.insert((x0, y));
-                }
-                sorted_vertical_edges.push((coords1, coords2));
+            let coverage = if entering { 1 } else { -1 };
+            if (x0, y0) == (x1, y1) {
+                return Err(format!(
+                    "Found repeated coordinate at indexes: {} and {}",
+                    i,
+                    i + 1
+                ));
+            } else if x0 == x1 {
+                vertical_edges.push((x0, y0.min(y1), y0.max(y1), coverage));
             } else if y0 == y1 {
-                let range = if x0 <= x1 { x0..=x1 } else { x1..=x0 };
-                for x in range {
-                    boundary_coords.insert((x, y0));
-                }
+                horizontal_edges.push((y0, x0.min(x1), x0.max(x1), coverage));
             } else {
                 return Err(format!(
                     "Found pair of coordinates that are not aligned at indexes: {} and {}",
@@ -119,95 +190,683 @@ impl Polygon {
                     i + 1
                 ));
             }
+            edge_rects.push((
+                x0.min(x1) - 1,
+                x0.max(x1) + 1,
+                y0.min(y1) - 1,
+                y0.max(y1) + 1,
+            ));
         }
-        sorted_vertical_edges.sort_by(|a, b| a.0.0.cmp(&b.0.0));
-        let mut rev_sorted_vertical_edges = sorted_vertical_edges.clone();
-        rev_sorted_vertical_edges.sort_by(|a, b| b.0.0.cmp(&a.0.0));
-        Ok(Self {
-            sorted_vertical_edges,
-            rev_sorted_vertical_edges,
-            boundary_coords,
-        })
+
+        let outline_vertical_edges = outline_edges(&edge_rects, &vertical_edges);
+        let transposed_rects: Vec<_> = edge_rects
+            .iter()
+            .map(|&(x0, x1, y0, y1)| (y0, y1, x0, x1))
+            .collect();
+        let outline_horizontal_edges = outline_edges(&transposed_rects, &horizontal_edges);
+        let mut outline_vertices: Vec<_> = outline_vertical_edges
+            .iter()
+            .flat_map(|&(x, lo, hi)| [(x, lo), (x, hi)])
+            .chain(
+                outline_horizontal_edges
+                    .iter()
+                    .flat_map(|&(y, lo, hi)| [(lo, y), (hi, y)]),
+            )
+            .collect();
+        outline_vertices.sort();
+        outline_vertices.dedup();
+
+        let mut vertices_by_x: Vec<_> = coordinates
+            .iter()
+            .enumerate()
+            .map(|(i, &(x, y))| (x, y, i))
+            .collect();
+        vertices_by_x.sort();
+        let mut polygon = Self {
+            vertices: coordinates.to_vec(),
+            reach: vec![],
+            vertices_by_x,
+            outline_vertical_edges: EdgeTree::new(outline_vertical_edges),
+            outline_horizontal_edges: EdgeTree::new(outline_horizontal_edges),
+            outline_vertices,
+        };
+        polygon.reach = (0..coordinates.len())
+            .map(|i| [0, 1, 2, 3].map(|direction| polygon.cast_ray(i, direction)))
+            .collect();
+        Ok(polygon)
     }
 
-    fn rect_entirely_inside(&self, coordinate_pair: ((usize, usize), (usize, usize))) -> bool {
-        let ((x0, y0), (x1, y1)) = coordinate_pair;
-        let (min_x, max_x) = if x0 < x1 { (x0, x1) } else { (x1, x0) };
-        let (min_y, max_y) = if y0 < y1 { (y0, y1) } else { (y1, y0) };
-        // Because I know about the input file, can just check the side edges of the rect and not the center.
-        // Realistically this should be made more robust and then also sped up.
-        for y in min_y..=max_y {
-            if !self.inside_boundary((min_x, y)) {
-                return false;
+    /// The furthest a rectangle with a corner at vertex `i` could extend into quadrant `k`, as
+    /// (width, height) in tiles. Both sides of the rectangle that touch the corner have to lie in
+    /// the polygon, so it can't go any further than we could walk along either of them.
+    fn quadrant_reach(&self, i: usize, k: usize) -> (usize, usize) {
+        // Quadrant k lies between axis directions k and k + 1, the even one of which is along x.
+        let (a, b) = (self.reach[i][k], self.reach[i][(k + 1) % 4]);
+        if k.is_multiple_of(2) { (a, b) } else { (b, a) }
+    }
+
+    // Walk from vertex `i` in an axis direction (0: +x, 1: +y, 2: -x, 3: -y) and return how many
+    // tiles we get past before we would step outside the polygon.
+    fn cast_ray(&self, i: usize, direction: usize) -> usize {
+        let (x, y) = doubled(self.vertices[i]);
+        let (edges, along, across) = if direction.is_multiple_of(2) {
+            (&self.outline_vertical_edges, x, y)
+        } else {
+            (&self.outline_horizontal_edges, y, x)
+        };
+        // Every tile sits inside the grown polygon, so there is always an edge to hit.
+        let hit = if direction < 2 {
+            edges.first_spanning(along + 1, usize::MAX, across, across)
+        } else {
+            edges.last_spanning(0, along - 1, across, across)
+        };
+        hit.map_or(0, |(key, _, _)| (key.abs_diff(along) - 1) / 2)
+    }
+
+    /// The largest area of a rectangle in the polygon with a corner at vertex `i` and the opposite
+    /// corner at another vertex in quadrant `k`, at most `reach` away, or `to_beat` if none is
+    /// larger.
+    ///
+    /// The sides of the rectangle touching vertex `i` are within the reach, so the outline can only
+    /// get into the rectangle by coming in through the other two sides, and has to turn back
+    /// before it gets to the sides touching vertex `i`. This means the rectangle fits as long as
+    /// there are no ends of outline edges in it. We go through the vertices and the ends of
+    /// outline edges in order of how far they are from vertex `i` along x, keeping track of how
+    /// far the rectangle could still reach along y without including any ends, and stop once
+    /// that can't beat `to_beat`.
+    fn largest_rect_area_from_corner(
+        &self,
+        i: usize,
+        k: usize,
+        reach: (usize, usize),
+        to_beat: usize,
+    ) -> usize {
+        let (x, y) = doubled(self.vertices[i]);
+        let (forwards, upwards) = (k == 0 || k == 3, k < 2);
+        let (x_min, x_max) = if forwards {
+            (x, x + 2 * reach.0)
+        } else {
+            (x - 2 * reach.0, x)
+        };
+        let vertices = {
+            let start = self
+                .vertices_by_x
+                .partition_point(|v| doubled((v.0, v.1)).0 < x_min);
+            let end = self
+                .vertices_by_x
+                .partition_point(|v| doubled((v.0, v.1)).0 <= x_max);
+            start..end
+        };
+        let ends = {
+            let start = self.outline_vertices.partition_point(|v| v.0 < x_min);
+            let end = self.outline_vertices.partition_point(|v| v.0 <= x_max);
+            start..end
+        };
+        // Take the nth closest along x from a range sorted by x.
+        let nth_closest = |range: &std::ops::Range<usize>, n: usize| {
+            if forwards {
+                range.start + n
+            } else {
+                range.end - 1 - n
+            }
+        };
+        // How far from vertex `i` a point is along y, if it's in the right direction.
+        let y_offset = |other_y: usize| {
+            if upwards {
+                other_y.checked_sub(y)
+            } else {
+                y.checked_sub(other_y)
+            }
+        };
+
+        let mut largest_area = to_beat;
+        // Everything in the rectangle has to be closer than this along y.
+        let mut y_limit = 2 * reach.1 + 1;
+        let (mut next_vertex, mut next_end) = (0, 0);
+        while next_vertex < vertices.len() {
+            let vertex = self.vertices_by_x[nth_closest(&vertices, next_vertex)];
+            let vertex = doubled((vertex.0, vertex.1));
+            let end = (next_end < ends.len())
+                .then(|| self.outline_vertices[nth_closest(&ends, next_end)]);
+            // Vertices and ends are never in the same place, since one is on odd coordinates and
+            // the other on even.
+            match end {
+                Some(end) if end.0.abs_diff(x) < vertex.0.abs_diff(x) => {
+                    next_end += 1;
+                    match y_offset(end.1) {
+                        Some(y_distance) if y_distance < y_limit => y_limit = y_distance,
+                        _ => continue,
+                    }
+                    if (reach.0 + 1) * ((y_limit - 1) / 2 + 1) <= largest_area {
+                        break;
+                    }
+                }
+                _ => {
+                    next_vertex += 1;
+                    if let Some(y_distance) = y_offset(vertex.1).filter(|&d| d < y_limit) {
+                        let area = (vertex.0.abs_diff(x) / 2 + 1) * (y_distance / 2 + 1);
+                        largest_area = largest_area.max(area);
+                    }
+                }
             }
         }
-        for y in min_y..=max_y {
-            if !self.inside_boundary((max_x, y)) {
-                return false;
+        largest_area
+    }
+}
+
+// Where a tile is in the doubled coordinates used for the grown polygon.
+fn doubled((x, y): (usize, usize)) -> (usize, usize) {
+    (2 * x + 1, 2 * y + 1)
+}
+
+/// The edges along one axis of the outline of everything covered by `rects`, given as
+/// (key_min, key_max, lo, hi), or by the polygon with edges `polygon_edges`, given as
+/// (key, lo, hi, coverage), where coverage is 1 if crossing the edge towards larger keys takes us
+/// into the polygon and -1 if it takes us out. Edges are returned as (key, lo, hi).
+///
+/// This sweeps across the keys keeping count of how many times each stretch of the line is
+/// covered. An outline edge is wherever a stretch goes from being uncovered to covered or back.
+fn outline_edges(
+    rects: &[(usize, usize, usize, usize)],
+    polygon_edges: &[(usize, usize, usize, i64)],
+) -> Vec<(usize, usize, usize)> {
+    // Each event is (key, lo, hi, change in coverage, whether it is the side of a rectangle).
+    let mut events: Vec<_> = polygon_edges
+        .iter()
+        .map(|&(key, lo, hi, coverage)| (key, lo, hi, coverage, false))
+        .collect();
+    for &(key_min, key_max, lo, hi) in rects {
+        events.push((key_min, lo, hi, 1, true));
+        events.push((key_max, lo, hi, -1, true));
+    }
+    events.sort();
+    let mut stops: Vec<usize> = events.iter().flat_map(|e| [e.1, e.2]).collect();
+    stops.sort();
+    stops.dedup();
+    let mut coverage = CoverageTree::new(stops);
+
+    let mut edges = vec![];
+    for group in events.chunk_by(|a, b| a.0 == b.0) {
+        let key = group[0].0;
+        let mut uncovered = vec![];
+        // Crossing the edge of the polygon never changes whether anything is covered, since it
+        // is always inside the rectangle around that edge. So only the rectangles matter here.
+        for &(_, lo, hi, change, is_rect) in group {
+            if is_rect && change > 0 {
+                coverage.uncovered(lo, hi, &mut uncovered);
+            }
+        }
+        for &(_, lo, hi, change, _) in group {
+            coverage.add(lo, hi, change);
+        }
+        for &(_, lo, hi, change, is_rect) in group {
+            if is_rect && change < 0 {
+                coverage.uncovered(lo, hi, &mut uncovered);
+            }
+        }
+        uncovered.sort();
+        let mut merged: Vec<(usize, usize)> = vec![];
+        for (lo, hi) in uncovered {
+            match merged.last_mut() {
+                Some(last) if lo <= last.1 => last.1 = last.1.max(hi),
+                _ => merged.push((lo, hi)),
             }
         }
-        true
+        edges.extend(merged.into_iter().map(|(lo, hi)| (key, lo, hi)));
+    }
+    edges
+}
+
+/// Counts how many times each stretch between consecutive stops along a line is covered, with
+/// ranges of stretches updated and searched in O(log n).
+struct CoverageTree {
+    stops: Vec<usize>,
+    // For each node, the amount added to all of it, and the smallest and largest count under it
+    // including that amount.
+    added: Vec<i64>,
+    min: Vec<i64>,
+    max: Vec<i64>,
+}
+
+impl CoverageTree {
+    fn new(stops: Vec<usize>) -> Self {
+        let size = 4 * stops.len().max(1);
+        Self {
+            stops,
+            added: vec![0; size],
+            min: vec![0; size],
+            max: vec![0; size],
+        }
+    }
+
+    fn stretches(&self, lo: usize, hi: usize) -> (usize, usize) {
+        (
+            self.stops.partition_point(|&stop| stop < lo),
+            self.stops.partition_point(|&stop| stop < hi),
+        )
+    }
+
+    /// Add `amount` to the count of everything from `lo` to `hi`.
+    fn add(&mut self, lo: usize, hi: usize, amount: i64) {
+        let (start, end) = self.stretches(lo, hi);
+        if start < end {
+            self.update(1, 0, self.stops.len() - 1, start, end, amount);
+        }
     }
 
-    fn inside_boundary(&self, coordinate: (usize, usize)) -> bool {
-        self.boundary_coords.contains(&coordinate)
-            || self.in_bounds_left(coordinate)
-            // A bit of a hack to fix a bug with false negatives. Only checking to one side should
-            // be sufficient.
-            || self.in_bounds_right(coordinate)
+    fn update(
+        &mut self,
+        node: usize,
+        node_start: usize,
+        node_end: usize,
+        start: usize,
+        end: usize,
+        amount: i64,
+    ) {
+        if end <= node_start || node_end <= start {
+            return;
+        }
+        if start <= node_start && node_end <= end {
+            self.added[node] += amount;
+            self.min[node] += amount;
+            self.max[node] += amount;
+            return;
+        }
+        let mid = (node_start + node_end) / 2;
+        self.update(2 * node, node_start, mid, start, end, amount);
+        self.update(2 * node + 1, mid, node_end, start, end, amount);
+        self.min[node] = self.added[node] + self.min[2 * node].min(self.min[2 * node + 1]);
+        self.max[node] = self.added[node] + self.max[2 * node].max(self.max[2 * node + 1]);
+    }
+
+    /// Push each stretch from `lo` to `hi` that isn't covered at all onto `found`, joining up any
+    /// that touch.
+    fn uncovered(&self, lo: usize, hi: usize, found: &mut Vec<(usize, usize)>) {
+        let (start, end) = self.stretches(lo, hi);
+        if start < end {
+            self.search(1, 0, self.stops.len() - 1, start, end, 0, found);
+        }
     }
 
-    // If we trace a ray to the left from a given point and count how many times it crosses a boundary, we can tell if it
-    // lies inside our polygon. If it crosses an even number it is outside, odd in.
-    fn in_bounds_left(&self, coordinate: (usize, usize)) -> bool {
-        let mut times_crossed_boundary = 0;
-        let mut prev_y0 = 0;
-        let mut prev_y1 = 0;
-        for &((x0, y0), (_, y1)) in &self.sorted_vertical_edges {
-            if x0 >= coordinate.0 {
-                break;
+    #[allow(clippy::too_many_arguments)]
+    fn search(
+        &self,
+        node: usize,
+        node_start: usize,
+        node_end: usize,
+        start: usize,
+        end: usize,
+        added_above: i64,
+        found: &mut Vec<(usize, usize)>,
+    ) {
+        if end <= node_start || node_end <= start || added_above + self.min[node] > 0 {
+            return;
+        }
+        if start <= node_start && node_end <= end && added_above + self.max[node] == 0 {
+            let (lo, hi) = (self.stops[node_start], self.stops[node_end]);
+            match found.last_mut() {
+                Some(last) if last.1 == lo => last.1 = hi,
+                _ => found.push((lo, hi)),
             }
-            if (y0 <= coordinate.1 && coordinate.1 <= y1)
-                || (y0 >= coordinate.1 && coordinate.1 >= y1)
-            {
-                // We are aligned perfectly with a horizontal line. This should count as one cross and not two.
-                let aligned_horizontally = (prev_y0 == y0 && prev_y0 == coordinate.1)
-                    || (prev_y1 == y1 && prev_y1 == coordinate.1);
-                if !aligned_horizontally {
-                    times_crossed_boundary += 1;
-                }
+            return;
+        }
+        let mid = (node_start + node_end) / 2;
+        let added_above = added_above + self.added[node];
+        self.search(2 * node, node_start, mid, start, end, added_above, found);
+        self.search(2 * node + 1, mid, node_end, start, end, added_above, found);
+    }
+}
+
+/// Edges stored as (key, lo, hi), where key is the fixed coordinate and lo..=hi the range the edge
+/// covers along the other axis.
+///
+/// This is a merge sort tree over the edges sorted by key. Each node holds the edges in its key
+/// range sorted by lo, along with a running maximum of hi, so we can find whether any edge in a
+/// key range covers a given span in O(log² n).
+struct EdgeTree {
+    edges: Vec<(usize, usize, usize)>,
+    // Each node holds (lo, running maximum of hi) for all edges under it.
+    nodes: Vec<Vec<(usize, usize)>>,
+}
+
+impl EdgeTree {
+    fn new(mut edges: Vec<(usize, usize, usize)>) -> Self {
+        edges.sort();
+        let mut nodes = vec![vec![]; 4 * edges.len().max(1)];
+        if !edges.is_empty() {
+            Self::build(&edges, &mut nodes, 1, 0, edges.len());
+        }
+        Self { edges, nodes }
+    }
+
+    fn build(
+        edges: &[(usize, usize, usize)],
+        nodes: &mut [Vec<(usize, usize)>],
+        node: usize,
+        start: usize,
+        end: usize,
+    ) {
+        let mut spans: Vec<(usize, usize)> = edges[start..end]
+            .iter()
+            .map(|&(_, lo, hi)| (lo, hi))
+            .collect();
+        spans.sort();
+        let mut max_hi = 0;
+        for span in spans.iter_mut() {
+            max_hi = max_hi.max(span.1);
+            span.1 = max_hi;
+        }
+        nodes[node] = spans;
+        if end - start > 1 {
+            let mid = (start + end) / 2;
+            Self::build(edges, nodes, 2 * node, start, mid);
+            Self::build(edges, nodes, 2 * node + 1, mid, end);
+        }
+    }
+
+    /// The edge with the smallest key in `key_min..=key_max` that has lo <= `lo` and hi >= `hi`.
+    fn first_spanning(
+        &self,
+        key_min: usize,
+        key_max: usize,
+        lo: usize,
+        hi: usize,
+    ) -> Option<(usize, usize, usize)> {
+        self.find_spanning(key_min, key_max, lo, hi, true)
+    }
+
+    /// The edge with the largest key in `key_min..=key_max` that has lo <= `lo` and hi >= `hi`.
+    fn last_spanning(
+        &self,
+        key_min: usize,
+        key_max: usize,
+        lo: usize,
+        hi: usize,
+    ) -> Option<(usize, usize, usize)> {
+        self.find_spanning(key_min, key_max, lo, hi, false)
+    }
+
+    fn find_spanning(
+        &self,
+        key_min: usize,
+        key_max: usize,
+        lo: usize,
+        hi: usize,
+        first: bool,
+    ) -> Option<(usize, usize, usize)> {
+        let start = self.edges.partition_point(|e| e.0 < key_min);
+        let end = self.edges.partition_point(|e| e.0 <= key_max);
+        if start >= end {
+            return None;
+        }
+        let mut found = None;
+        self.search(
+            1,
+            0,
+            self.edges.len(),
+            start,
+            end,
+            lo,
+            hi,
+            first,
+            &mut found,
+        );
+        found.map(|index| self.edges[index])
+    }
+
+    // Walk down the tree looking for a matching edge, visiting children in order so the first edge
+    // we find is the first (or last) one by key. Returns true once something has been found.
+    #[allow(clippy::too_many_arguments)]
+    fn search(
+        &self,
+        node: usize,
+        node_start: usize,
+        node_end: usize,
+        start: usize,
+        end: usize,
+        lo: usize,
+        hi: usize,
+        first: bool,
+        found: &mut Option<usize>,
+    ) -> bool {
+        if end <= node_start || node_end <= start {
+            return false;
+        }
+        if start <= node_start && node_end <= end {
+            let spans = &self.nodes[node];
+            let covering_lo = spans.partition_point(|s| s.0 <= lo);
+            if covering_lo == 0 || spans[covering_lo - 1].1 < hi {
+                return false;
+            }
+            if node_end - node_start == 1 {
+                *found = Some(node_start);
+                return true;
+            }
+        }
+        let mid = (node_start + node_end) / 2;
+        let children = [(2 * node, node_start, mid), (2 * node + 1, mid, node_end)];
+        let ordered = if first {
+            [children[0], children[1]]
+        } else {
+            [children[1], children[0]]
+        };
+        ordered.iter().any(|&(child, child_start, child_end)| {
+            self.search(
+                child,
+                child_start,
+                child_end,
+                start,
+                end,
+                lo,
+                hi,
+                first,
+                found,
+            )
+        })
+    }
+}
+
+/// A simple polygon where edges can go in any direction, not just horizontally or vertically.
+///
+/// Every check here is done with exact integer arithmetic, so points lying right on the boundary
+/// are always handled correctly, and the boundary counts as being inside.
+///
+/// Unlike [`Polygon`] this treats the polygon and rectangles as continuous shapes rather than as
+/// tiles, so a rectangle can never cross a gap between two edges, even one too narrow to hold any
+/// tiles. This means the two can give different answers even when every edge is horizontal or
+/// vertical.
+struct GeneralPolygon {
+    vertices: Vec<(i128, i128)>,
+}
+
+impl GeneralPolygon {
+    /// Checks that the coordinates make a simple polygon, that is no two edges touch other than
+    /// neighbouring edges at the vertex they share. This compares every pair of edges, so is
+    /// quadratic in the number of coordinates.
+    fn new(coordinates: &[(usize, usize)]) -> Result<Self, String> {
+        if coordinates.len() < 3 {
+            return Err(format!(
+                "Need at least 3 coordinates to make a polygon, got {}",
+                coordinates.len()
+            ));
+        }
+        let vertices: Vec<(i128, i128)> = coordinates
+            .iter()
+            .map(|&(x, y)| (x as i128, y as i128))
+            .collect();
+        let n = vertices.len();
+        for i in 0..n {
+            if vertices[i] == vertices[(i + 1) % n] {
+                return Err(format!(
+                    "Found repeated coordinate at indexes: {} and {}",
+                    i,
+                    i + 1
+                ));
             }
-            prev_y0 = y0;
-            prev_y1 = y1;
-        }
-        times_crossed_boundary % 2 != 0
-    }
-
-    // If we trace a ray to the right from a given point and count how many times it crosses a boundary, we can tell if it
-    // lies inside our polygon. If it crosses an even number it is outside, odd in.
-    fn in_bounds_right(&self, coordinate: (usize, usize)) -> bool {
-        let mut times_crossed_boundary = 0;
-        let mut prev_y0 = 0;
-        let mut prev_y1 = 0;
-        for &((x0, y0), (_, y1)) in &self.rev_sorted_vertical_edges {
-            if x0 <= coordinate.0 {
-                break;
-            }
-            if (y0 <= coordinate.1 && coordinate.1 <= y1)
-                || (y0 >= coordinate.1 && coordinate.1 >= y1)
-            {
-                // We are aligned perfectly with a horizontal line. This should count as one cross and not two.
-                let aligned_horizontally = (prev_y0 == y0 && prev_y0 == coordinate.1)
-                    || (prev_y1 == y1 && prev_y1 == coordinate.1);
-                if !aligned_horizontally {
-                    times_crossed_boundary += 1;
+        }
+        for i in 0..n {
+            let (a, b) = (vertices[i], vertices[(i + 1) % n]);
+            for j in i + 1..n {
+                let (c, d) = (vertices[j], vertices[(j + 1) % n]);
+                // Neighbouring edges always share a vertex, so are only a problem if one doubles
+                // back along the other.
+                let shared = if j == i + 1 {
+                    Some((a, b, d))
+                } else if i == 0 && j == n - 1 {
+                    Some((b, a, c))
+                } else {
+                    None
+                };
+                let touching = match shared {
+                    Some((p, s, q)) => {
+                        cross(p, s, q) == 0
+                            && (p.0 - s.0) * (q.0 - s.0) + (p.1 - s.1) * (q.1 - s.1) > 0
+                    }
+                    None => segments_intersect(a, b, c, d),
+                };
+                if touching {
+                    return Err(format!(
+                        "Edges starting at indexes {} and {} overlap, so the polygon is not simple",
+                        i, j
+                    ));
                 }
             }
-            prev_y0 = y0;
-            prev_y1 = y1;
         }
-        times_crossed_boundary % 2 != 0
+        Ok(Self { vertices })
+    }
+
+    fn edges(&self) -> impl Iterator<Item = ((i128, i128), (i128, i128))> + '_ {
+        (0..self.vertices.len()).map(|i| {
+            (
+                self.vertices[i],
+                self.vertices[(i + 1) % self.vertices.len()],
+            )
+        })
+    }
+
+    /// Whether a point lies on or within the polygon.
+    fn contains_point(&self, point: (i128, i128)) -> bool {
+        contains_scaled_point(&self.vertices, point, 1)
+    }
+
+    /// Whether the whole of a rectangle lies on or within the polygon.
+    ///
+    /// If nothing on the boundary of the polygon passes through the open interior of the
+    /// rectangle, then the interior must be either entirely inside or entirely outside, so we
+    /// only need to check a single point such as the centre.
+    fn contains_rect(&self, rect: &Rect) -> bool {
+        let (x0, y0) = (rect.x0 as i128, rect.y0 as i128);
+        let (x1, y1) = (rect.x1 as i128, rect.y1 as i128);
+        if x0 == x1 && y0 == y1 {
+            return self.contains_point((x0, y0));
+        }
+        if y0 == y1 {
+            return contains_horizontal_segment(&self.vertices, y0, x0, x1);
+        }
+        if x0 == x1 {
+            let flipped: Vec<_> = self.vertices.iter().map(|&(x, y)| (y, x)).collect();
+            return contains_horizontal_segment(&flipped, x0, y0, y1);
+        }
+        let corners = [(x0, y0), (x1, y0), (x1, y1), (x0, y1)];
+        let boundary_passes_through = self.edges().any(|(a, b)| {
+            // Separating axis test, the segment misses the open rectangle if we can fit a line
+            // between them along either axis or along the segment itself.
+            let separated_by_axis = a.0.max(b.0) <= x0
+                || a.0.min(b.0) >= x1
+                || a.1.max(b.1) <= y0
+                || a.1.min(b.1) >= y1;
+            let sides = corners.map(|corner| cross(a, b, corner).signum());
+            let separated_by_segment =
+                sides.iter().all(|&side| side >= 0) || sides.iter().all(|&side| side <= 0);
+            !separated_by_axis && !separated_by_segment
+        });
+        !boundary_passes_through && contains_scaled_point(&self.vertices, (x0 + x1, y0 + y1), 2)
+    }
+}
+
+// Twice the signed area of the triangle a, b, c. Positive when c is to the left of the line
+// going from a to b, negative when to the right and zero when all three are in a line.
+fn cross(a: (i128, i128), b: (i128, i128), c: (i128, i128)) -> i128 {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+// Whether c, which must be in line with a and b, lies between them.
+fn within_segment(a: (i128, i128), b: (i128, i128), c: (i128, i128)) -> bool {
+    a.0.min(b.0) <= c.0 && c.0 <= a.0.max(b.0) && a.1.min(b.1) <= c.1 && c.1 <= a.1.max(b.1)
+}
+
+/// Whether the closed segments from a to b and from c to d share any point.
+fn segments_intersect(a: (i128, i128), b: (i128, i128), c: (i128, i128), d: (i128, i128)) -> bool {
+    let a_side = cross(c, d, a).signum();
+    let b_side = cross(c, d, b).signum();
+    let c_side = cross(a, b, c).signum();
+    let d_side = cross(a, b, d).signum();
+    if a_side * b_side < 0 && c_side * d_side < 0 {
+        return true;
+    }
+    (a_side == 0 && within_segment(c, d, a))
+        || (b_side == 0 && within_segment(c, d, b))
+        || (c_side == 0 && within_segment(a, b, c))
+        || (d_side == 0 && within_segment(a, b, d))
+}
+
+// Winding number test for the point (x / scale, y / scale), which lets us check points that don't
+// lie exactly on the grid. Points on the boundary count as inside.
+fn contains_scaled_point(vertices: &[(i128, i128)], point: (i128, i128), scale: i128) -> bool {
+    let mut winding = 0;
+    for i in 0..vertices.len() {
+        let (x0, y0) = vertices[i];
+        let (x1, y1) = vertices[(i + 1) % vertices.len()];
+        let a = (x0 * scale, y0 * scale);
+        let b = (x1 * scale, y1 * scale);
+        let side = cross(a, b, point);
+        if side == 0 && within_segment(a, b, point) {
+            return true;
+        }
+        if a.1 <= point.1 {
+            if b.1 > point.1 && side > 0 {
+                winding += 1;
+            }
+        } else if b.1 <= point.1 && side < 0 {
+            winding -= 1;
+        }
+    }
+    winding != 0
+}
+
+// Whether the segment along row y from x0 to x1 lies on or within the polygon. We find every x
+// where the boundary meets the row, then since the segment can only go in or out of the polygon at
+// those points, check a point half way between each of them.
+fn contains_horizontal_segment(vertices: &[(i128, i128)], y: i128, x0: i128, x1: i128) -> bool {
+    if x0 == x1 {
+        return contains_scaled_point(vertices, (x0, y), 1);
+    }
+    // Stored as fractions (numerator, denominator) with a positive denominator.
+    let mut stops = vec![(x0, 1), (x1, 1)];
+    for i in 0..vertices.len() {
+        let a = vertices[i];
+        let b = vertices[(i + 1) % vertices.len()];
+        if a.1 == y && b.1 == y {
+            stops.push((a.0, 1));
+            stops.push((b.0, 1));
+        } else if (a.1 - y) * (b.1 - y) <= 0 {
+            let (mut numerator, mut denominator) =
+                (a.0 * (b.1 - a.1) + (y - a.1) * (b.0 - a.0), b.1 - a.1);
+            if denominator < 0 {
+                numerator = -numerator;
+                denominator = -denominator;
+            }
+            stops.push((numerator, denominator));
+        }
     }
+    stops.retain(|&(n, d)| x0 * d <= n && n <= x1 * d);
+    stops.sort_by(|&(n1, d1), &(n2, d2)| (n1 * d2).cmp(&(n2 * d1)));
+    stops.dedup_by(|&mut (n1, d1), &mut (n2, d2)| n1 * d2 == n2 * d1);
+    stops.windows(2).all(|w| {
+        let ((n1, d1), (n2, d2)) = (w[0], w[1]);
+        let scale = 2 * d1 * d2;
+        contains_scaled_point(vertices, (n1 * d2 + n2 * d1, y * scale), scale)
+    })
 }
 
 struct Rect {
@@ -234,3 +893,147 @@ impl Rect {
         ((self.x1 - self.x0) + 1) * ((self.y1 - self.y0) + 1)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn largest_rect_area_in_polygon(coordinates: &[(usize, usize)]) -> usize {
+        let polygon = Polygon::new(coordinates).expect("coordinates should make a polygon");
+        get_largest_rect_area_entirely_in_polygon(&polygon)
+    }
+
+    #[test]
+    fn example_input() {
+        let coordinates = parse_input("inputs/9test.txt").expect("example input should parse");
+        assert_eq!(get_largest_rect_area(&coordinates), 50);
+        assert_eq!(largest_rect_area_in_polygon(&coordinates), 24);
+    }
+
+    #[test]
+    fn rect_can_cross_gap_with_no_tiles_in_it() {
+        // The slot between x = 5 and x = 6 is outside the loop, but every tile is on or inside it.
+        let coordinates = [
+            (0, 0),
+            (10, 0),
+            (10, 10),
+            (6, 10),
+            (6, 2),
+            (5, 2),
+            (5, 10),
+            (0, 10),
+        ];
+        assert_eq!(largest_rect_area_in_polygon(&coordinates), 121);
+    }
+
+    #[test]
+    fn rect_cant_cross_gap_with_tiles_in_it() {
+        // Widening the slot leaves tiles at x = 6 outside the loop.
+        let coordinates = [
+            (0, 0),
+            (10, 0),
+            (10, 10),
+            (7, 10),
+            (7, 2),
+            (5, 2),
+            (5, 10),
+            (0, 10),
+        ];
+        assert_eq!(largest_rect_area_in_polygon(&coordinates), 66);
+    }
+
+    fn general_polygon(coordinates: &[(usize, usize)]) -> GeneralPolygon {
+        GeneralPolygon::new(coordinates).expect("coordinates should make a simple polygon")
+    }
+
+    const DIAMOND: [(usize, usize); 4] = [(0, 5), (5, 0), (10, 5), (5, 10)];
+
+    #[test]
+    fn general_polygon_treats_gaps_as_outside() {
+        // The same slot as above, which only counting by tiles lets a rectangle cross.
+        let coordinates = [
+            (0, 0),
+            (10, 0),
+            (10, 10),
+            (6, 10),
+            (6, 2),
+            (5, 2),
+            (5, 10),
+            (0, 10),
+        ];
+        let polygon = general_polygon(&coordinates);
+        assert_eq!(
+            get_largest_rect_area_entirely_in_general_polygon(&coordinates, &polygon),
+            66
+        );
+    }
+
+    #[test]
+    fn largest_rect_in_diamond() {
+        let polygon = general_polygon(&DIAMOND);
+        // Only the diagonals fit between a pair of corners.
+        assert_eq!(
+            get_largest_rect_area_entirely_in_general_polygon(&DIAMOND, &polygon),
+            11
+        );
+    }
+
+    #[test]
+    fn general_polygon_contains_rect() {
+        let diamond = general_polygon(&DIAMOND);
+        assert!(diamond.contains_rect(&Rect::new((3, 3), (7, 7))));
+        assert!(!diamond.contains_rect(&Rect::new((2, 2), (8, 8))));
+        // Corners right on the boundary count as inside.
+        assert!(diamond.contains_rect(&Rect::new((3, 3), (7, 6))));
+        assert!(diamond.contains_rect(&Rect::new((0, 5), (10, 5))));
+        assert!(diamond.contains_rect(&Rect::new((5, 0), (5, 10))));
+        assert!(!diamond.contains_rect(&Rect::new((1, 1), (1, 9))));
+        assert!(diamond.contains_rect(&Rect::new((5, 0), (5, 0))));
+        assert!(!diamond.contains_rect(&Rect::new((0, 0), (0, 0))));
+
+        let triangle = general_polygon(&[(0, 0), (10, 0), (0, 10)]);
+        assert!(triangle.contains_rect(&Rect::new((0, 0), (5, 5))));
+        assert!(!triangle.contains_rect(&Rect::new((0, 0), (6, 5))));
+        assert!(!triangle.contains_rect(&Rect::new((0, 10), (10, 0))));
+    }
+
+    #[test]
+    fn horizontal_segment_in_diamond() {
+        let vertices: Vec<_> = DIAMOND
+            .iter()
+            .map(|&(x, y)| (x as i128, y as i128))
+            .collect();
+        assert!(contains_horizontal_segment(&vertices, 5, 0, 10));
+        assert!(contains_horizontal_segment(&vertices, 2, 3, 7));
+        assert!(!contains_horizontal_segment(&vertices, 2, 2, 7));
+        assert!(contains_horizontal_segment(&vertices, 0, 5, 5));
+        assert!(!contains_horizontal_segment(&vertices, 11, 5, 5));
+    }
+
+    #[test]
+    fn segment_intersections() {
+        assert!(segments_intersect((0, 0), (4, 4), (0, 4), (4, 0)));
+        // Touching at an end, or one ending part way along the other.
+        assert!(segments_intersect((0, 0), (2, 2), (2, 2), (4, 0)));
+        assert!(segments_intersect((0, 0), (4, 0), (2, 0), (2, 3)));
+        // In line, overlapping and not.
+        assert!(segments_intersect((0, 0), (4, 4), (2, 2), (6, 6)));
+        assert!(!segments_intersect((0, 0), (1, 1), (2, 2), (3, 3)));
+        assert!(!segments_intersect((0, 0), (4, 0), (0, 1), (4, 1)));
+        assert!(!segments_intersect((0, 0), (4, 4), (3, 0), (5, 1)));
+    }
+
+    #[test]
+    fn general_polygon_must_be_simple() {
+        assert!(GeneralPolygon::new(&DIAMOND).is_ok());
+        assert!(GeneralPolygon::new(&[(0, 0), (10, 0), (0, 10)]).is_ok());
+        assert!(GeneralPolygon::new(&[(0, 0), (10, 0)]).is_err());
+        // A bow tie, whose edges cross in the middle.
+        assert!(GeneralPolygon::new(&[(0, 0), (10, 10), (10, 0), (0, 10)]).is_err());
+        // An edge doubling back along the one before it.
+        assert!(GeneralPolygon::new(&[(0, 0), (10, 0), (5, 0), (5, 5)]).is_err());
+        // Two edges that meet at a point that isn't a shared vertex.
+        assert!(GeneralPolygon::new(&[(0, 0), (10, 0), (10, 10), (5, 0), (0, 10)]).is_err());
+        assert!(GeneralPolygon::new(&[(0, 0), (0, 0), (10, 0), (0, 10)]).is_err());
+    }
+}
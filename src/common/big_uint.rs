@@ -0,0 +1,324 @@
+//! An unsigned integer with as many digits as it needs, shared by the days that can outgrow the
+//! built in integer types.
+
+// Each day only uses some of what is here.
+#![allow(dead_code)]
+
+use std::{cmp::Ordering, fmt};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    // Base 2^32 digits, least significant first, with no trailing zeros.
+    digits: Vec<u32>,
+}
+
+impl BigUint {
+    fn normalised(mut digits: Vec<u32>) -> Self {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        Self { digits }
+    }
+
+    pub fn from_u64(value: u64) -> Self {
+        Self::normalised(vec![value as u32, (value >> 32) as u32])
+    }
+
+    /// The value as a u64, or None if it is too big.
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.digits.as_slice() {
+            [] => Some(0),
+            [low] => Some(*low as u64),
+            [low, high] => Some((*high as u64) << 32 | *low as u64),
+            _ => None,
+        }
+    }
+
+    /// Build a number from base 2^32 digits, least significant first.
+    pub fn from_digits(digits: Vec<u32>) -> Self {
+        Self::normalised(digits)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    /// Read a number written in the given base, using letters for digits past 9.
+    pub fn from_str_radix(digits: &str, radix: u32) -> Result<Self, String> {
+        if digits.is_empty() {
+            return Err("cannot parse integer from empty string".to_owned());
+        }
+        digits.chars().try_fold(Self::from_u64(0), |number, c| {
+            let digit = c
+                .to_digit(radix)
+                .ok_or(format!("invalid digit {:?} found in string", c))?;
+            Ok(number.mul_add_small(radix, digit))
+        })
+    }
+
+    /// Write the number in the given base, using letters for digits past 9.
+    pub fn to_string_radix(&self, radix: u32) -> String {
+        // Pull off as many digits at a time as fit in a u32.
+        let (mut chunk, mut chunk_len) = (radix, 1);
+        while let Some(bigger) = chunk.checked_mul(radix) {
+            chunk = bigger;
+            chunk_len += 1;
+        }
+        let mut number = self.clone();
+        let mut digits = vec![];
+        loop {
+            let (quotient, mut remainder) = number.div_rem_small(chunk);
+            number = quotient;
+            for _ in 0..chunk_len {
+                digits.push(char::from_digit(remainder % radix, radix).unwrap_or('?'));
+                remainder /= radix;
+                if number.is_zero() && remainder == 0 {
+                    break;
+                }
+            }
+            if number.is_zero() {
+                break;
+            }
+        }
+        digits.iter().rev().collect()
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        let mut digits = Vec::with_capacity(self.digits.len().max(other.digits.len()) + 1);
+        let mut carry = 0;
+        for i in 0..self.digits.len().max(other.digits.len()) {
+            let sum = self.digits.get(i).copied().unwrap_or(0) as u64
+                + other.digits.get(i).copied().unwrap_or(0) as u64
+                + carry;
+            digits.push(sum as u32);
+            carry = sum >> 32;
+        }
+        digits.push(carry as u32);
+        Self::normalised(digits)
+    }
+
+    /// The difference, or None if `other` is bigger.
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        if self < other {
+            return None;
+        }
+        let mut digits = Vec::with_capacity(self.digits.len());
+        let mut borrow = 0;
+        for (i, &digit) in self.digits.iter().enumerate() {
+            let subtrahend = other.digits.get(i).copied().unwrap_or(0) as i64 + borrow;
+            let mut difference = digit as i64 - subtrahend;
+            borrow = 0;
+            if difference < 0 {
+                difference += 1 << 32;
+                borrow = 1;
+            }
+            digits.push(difference as u32);
+        }
+        Some(Self::normalised(digits))
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        let mut digits = vec![0u32; self.digits.len() + other.digits.len()];
+        for (i, &a) in self.digits.iter().enumerate() {
+            let mut carry = 0;
+            for (j, &b) in other.digits.iter().enumerate() {
+                let product = a as u64 * b as u64 + digits[i + j] as u64 + carry;
+                digits[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            digits[i + other.digits.len()] = carry as u32;
+        }
+        Self::normalised(digits)
+    }
+
+    /// Multiply by a small number and add another, as when reading digits one at a time.
+    pub fn mul_add_small(&self, multiplier: u32, addend: u32) -> Self {
+        let mut digits = Vec::with_capacity(self.digits.len() + 1);
+        let mut carry = addend as u64;
+        for &digit in &self.digits {
+            let value = digit as u64 * multiplier as u64 + carry;
+            digits.push(value as u32);
+            carry = value >> 32;
+        }
+        digits.push(carry as u32);
+        Self::normalised(digits)
+    }
+
+    /// The quotient and remainder, or None when dividing by zero.
+    // Long division one bit at a time, which is plenty fast enough for the sizes we deal with.
+    pub fn div_rem(&self, other: &Self) -> Option<(Self, Self)> {
+        if other.is_zero() {
+            return None;
+        }
+        let mut quotient = vec![0u32; self.digits.len()];
+        let mut remainder = Self::from_u64(0);
+        for index in (0..self.bit_len()).rev() {
+            remainder = remainder.mul_add_small(2, self.bit(index) as u32);
+            if remainder >= *other {
+                remainder = remainder.checked_sub(other)?;
+                quotient[index / 32] |= 1 << (index % 32);
+            }
+        }
+        Some((Self::normalised(quotient), remainder))
+    }
+
+    /// Divide by a small number that isn't zero, returning the quotient and remainder.
+    pub fn div_rem_small(&self, divisor: u32) -> (Self, u32) {
+        let mut digits = self.digits.clone();
+        let mut remainder = 0;
+        for digit in digits.iter_mut().rev() {
+            let value = (remainder << 32) | *digit as u64;
+            *digit = (value / divisor as u64) as u32;
+            remainder = value % divisor as u64;
+        }
+        (Self::normalised(digits), remainder as u32)
+    }
+
+    /// The closest floating point number, for working out proportions.
+    pub fn to_f64(&self) -> f64 {
+        self.digits
+            .iter()
+            .rev()
+            .fold(0.0, |value, &digit| value * 2f64.powi(32) + digit as f64)
+    }
+
+    /// The number divided by 2^`bits`, rounding down.
+    pub fn shift_right(&self, bits: usize) -> Self {
+        let digits = self.digits.get(bits / 32..).unwrap_or_default();
+        let shift = bits % 32;
+        if shift == 0 {
+            return Self::normalised(digits.to_vec());
+        }
+        let shifted = (0..digits.len())
+            .map(|i| {
+                let above = digits.get(i + 1).map_or(0, |&digit| digit << (32 - shift));
+                digits[i] >> shift | above
+            })
+            .collect();
+        Self::normalised(shifted)
+    }
+
+    /// How many bits the number has, with zero having none.
+    pub fn bit_len(&self) -> usize {
+        self.digits.last().map_or(0, |&top| {
+            (self.digits.len() - 1) * 32 + (32 - top.leading_zeros() as usize)
+        })
+    }
+
+    fn bit(&self, index: usize) -> bool {
+        self.digits
+            .get(index / 32)
+            .is_some_and(|digit| digit >> (index % 32) & 1 == 1)
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.digits
+            .len()
+            .cmp(&other.digits.len())
+            .then_with(|| self.digits.iter().rev().cmp(other.digits.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&self.to_string_radix(10))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(digits: &str) -> BigUint {
+        BigUint::from_str_radix(digits, 10).expect("test numbers should parse")
+    }
+
+    #[test]
+    fn add_carries_across_digits() {
+        assert_eq!(
+            BigUint::from_u64(u64::MAX).add(&BigUint::from_u64(1)),
+            big("18446744073709551616")
+        );
+        assert_eq!(big("0").add(&big("0")), big("0"));
+    }
+
+    #[test]
+    fn sub_borrows_across_digits() {
+        assert_eq!(
+            big("18446744073709551616").checked_sub(&BigUint::from_u64(1)),
+            Some(BigUint::from_u64(u64::MAX))
+        );
+        assert_eq!(big("5").checked_sub(&big("5")), Some(big("0")));
+        assert_eq!(big("5").checked_sub(&big("6")), None);
+    }
+
+    #[test]
+    fn mul_matches_u128() {
+        let (a, b) = (0xdead_beef_1234_5678_u64, 0xffff_ffff_ffff_fff1_u64);
+        assert_eq!(
+            BigUint::from_u64(a).mul(&BigUint::from_u64(b)),
+            big(&(a as u128 * b as u128).to_string())
+        );
+        assert_eq!(big("123456789").mul(&big("0")), big("0"));
+    }
+
+    #[test]
+    fn div_rem_matches_u128() {
+        let (a, b) = (
+            0x1234_5678_9abc_def0_1122_3344_5566_7788_u128,
+            0x9_8765_4321_u128,
+        );
+        let (quotient, remainder) = big(&a.to_string())
+            .div_rem(&big(&b.to_string()))
+            .expect("divisor isn't zero");
+        assert_eq!(quotient, big(&(a / b).to_string()));
+        assert_eq!(remainder, big(&(a % b).to_string()));
+        assert_eq!(big("7").div_rem(&big("0")), None);
+        assert_eq!(big("7").div_rem(&big("9")), Some((big("0"), big("7"))));
+    }
+
+    #[test]
+    fn radix_formatting_round_trips() {
+        let digits = "123456789012345678901234567890123456789";
+        assert_eq!(big(digits).to_string(), digits);
+        assert_eq!(big("0").to_string(), "0");
+        assert_eq!(BigUint::from_u64(255).to_string_radix(16), "ff");
+        assert_eq!(BigUint::from_u64(5).to_string_radix(2), "101");
+        assert_eq!(
+            BigUint::from_str_radix("zz1000000000000000000000", 36).map(|n| n.to_string_radix(36)),
+            Ok("zz1000000000000000000000".to_owned())
+        );
+        assert!(BigUint::from_str_radix("12a", 10).is_err());
+        assert!(BigUint::from_str_radix("", 10).is_err());
+    }
+
+    #[test]
+    fn digits_and_bit_length() {
+        assert_eq!(BigUint::from_digits(vec![5, 0, 0]), BigUint::from_u64(5));
+        assert_eq!(BigUint::from_digits(vec![0, 1]), BigUint::from_u64(1 << 32));
+        assert_eq!(BigUint::from_digits(vec![]).bit_len(), 0);
+        assert_eq!(BigUint::from_u64(1).bit_len(), 1);
+        assert_eq!(BigUint::from_u64(u64::MAX).bit_len(), 64);
+        assert_eq!(big("18446744073709551616").bit_len(), 65);
+    }
+
+    #[test]
+    fn shift_right_matches_u128() {
+        let value: u128 = 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210;
+        for bits in [0, 1, 31, 32, 33, 64, 100, 127, 128, 200] {
+            let expected = value.checked_shr(bits).unwrap_or(0);
+            assert_eq!(
+                big(&value.to_string()).shift_right(bits as usize),
+                big(&expected.to_string())
+            );
+        }
+    }
+}
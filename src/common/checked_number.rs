@@ -0,0 +1,240 @@
+//! Numbers that refuse to silently wrap around if they get too big, shared by the days that can
+//! outgrow the built in integer types. Needs the `big_uint` module to be included alongside it.
+
+// Each day only uses some of what is here.
+#![allow(dead_code)]
+
+use crate::big_uint::BigUint;
+use std::{fmt, num::IntErrorKind};
+
+/// A number that only does arithmetic that can fail rather than overflow, so the same code can
+/// run with a built in integer type or with a [`BigUint`] when that isn't big enough.
+pub trait CheckedNumber: Clone + Ord + fmt::Display {
+    /// What to call this type in error messages.
+    const NAME: &str;
+
+    fn from_u64(value: u64) -> Self;
+    fn from_str_radix(digits: &str, radix: u32) -> Result<Self, ParseNumberError>;
+    /// Write the number in the given base, using letters for digits past 9.
+    fn to_string_radix(&self, radix: u32) -> String;
+    /// Returns None if the sum is too big to represent.
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    /// Returns None if `other` is bigger.
+    fn checked_sub(&self, other: &Self) -> Option<Self>;
+    /// Returns None if the product is too big to represent.
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+    /// The quotient and remainder, or None when dividing by zero.
+    fn div_rem(&self, other: &Self) -> Option<(Self, Self)>;
+
+    fn zero() -> Self {
+        Self::from_u64(0)
+    }
+
+    fn one() -> Self {
+        Self::from_u64(1)
+    }
+
+    /// How many digits the number has in the given base, with zero having one.
+    fn digit_count(&self, radix: u32) -> u32 {
+        self.to_string_radix(radix).len() as u32
+    }
+}
+
+/// Why a number couldn't be read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseNumberError {
+    /// It isn't a number written in the expected base.
+    Invalid(String),
+    /// It is too big for the type of number being used.
+    TooBig,
+}
+
+impl fmt::Display for ParseNumberError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseNumberError::Invalid(e) => write!(f, "{}", e),
+            ParseNumberError::TooBig => write!(f, "number too large to fit in target type"),
+        }
+    }
+}
+
+impl CheckedNumber for u64 {
+    const NAME: &str = "u64";
+
+    fn from_u64(value: u64) -> Self {
+        value
+    }
+
+    fn from_str_radix(digits: &str, radix: u32) -> Result<Self, ParseNumberError> {
+        u64::from_str_radix(digits, radix).map_err(|e| match e.kind() {
+            IntErrorKind::PosOverflow => ParseNumberError::TooBig,
+            _ => ParseNumberError::Invalid(e.to_string()),
+        })
+    }
+
+    fn to_string_radix(&self, radix: u32) -> String {
+        u128_to_string_radix(*self as u128, radix)
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        u64::checked_add(*self, *other)
+    }
+
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        u64::checked_sub(*self, *other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        u64::checked_mul(*self, *other)
+    }
+
+    fn div_rem(&self, other: &Self) -> Option<(Self, Self)> {
+        Some((self.checked_div(*other)?, self.checked_rem(*other)?))
+    }
+
+    fn digit_count(&self, radix: u32) -> u32 {
+        (*self).max(1).ilog(radix as u64) + 1
+    }
+}
+
+impl CheckedNumber for u128 {
+    const NAME: &str = "u128";
+
+    fn from_u64(value: u64) -> Self {
+        value as u128
+    }
+
+    fn from_str_radix(digits: &str, radix: u32) -> Result<Self, ParseNumberError> {
+        u128::from_str_radix(digits, radix).map_err(|e| match e.kind() {
+            IntErrorKind::PosOverflow => ParseNumberError::TooBig,
+            _ => ParseNumberError::Invalid(e.to_string()),
+        })
+    }
+
+    fn to_string_radix(&self, radix: u32) -> String {
+        u128_to_string_radix(*self, radix)
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        u128::checked_add(*self, *other)
+    }
+
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        u128::checked_sub(*self, *other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        u128::checked_mul(*self, *other)
+    }
+
+    fn div_rem(&self, other: &Self) -> Option<(Self, Self)> {
+        Some((self.checked_div(*other)?, self.checked_rem(*other)?))
+    }
+
+    fn digit_count(&self, radix: u32) -> u32 {
+        (*self).max(1).ilog(radix as u128) + 1
+    }
+}
+
+impl CheckedNumber for BigUint {
+    const NAME: &str = "a big integer";
+
+    fn from_u64(value: u64) -> Self {
+        BigUint::from_u64(value)
+    }
+
+    fn from_str_radix(digits: &str, radix: u32) -> Result<Self, ParseNumberError> {
+        BigUint::from_str_radix(digits, radix).map_err(ParseNumberError::Invalid)
+    }
+
+    fn to_string_radix(&self, radix: u32) -> String {
+        BigUint::to_string_radix(self, radix)
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self.add(other))
+    }
+
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        BigUint::checked_sub(self, other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self.mul(other))
+    }
+
+    fn div_rem(&self, other: &Self) -> Option<(Self, Self)> {
+        BigUint::div_rem(self, other)
+    }
+}
+
+fn u128_to_string_radix(mut number: u128, radix: u32) -> String {
+    let mut digits = vec![];
+    loop {
+        let digit = (number % radix as u128) as u32;
+        digits.push(char::from_digit(digit, radix).unwrap_or('?'));
+        number /= radix as u128;
+        if number == 0 {
+            break;
+        }
+    }
+    digits.iter().rev().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Run the same arithmetic with each type and check they agree while nothing overflows.
+    fn arithmetic<T: CheckedNumber>(a: u64, b: u64) -> Vec<Option<String>> {
+        let (a, b) = (T::from_u64(a), T::from_u64(b));
+        vec![
+            a.checked_add(&b).map(|n| n.to_string()),
+            a.checked_sub(&b).map(|n| n.to_string()),
+            a.checked_mul(&b).map(|n| n.to_string()),
+            a.div_rem(&b).map(|(q, r)| format!("{} {}", q, r)),
+        ]
+    }
+
+    #[test]
+    fn types_agree() {
+        for (a, b) in [(0, 0), (7, 3), (3, 7), (1 << 31, 1 << 31), (999_999, 1000)] {
+            let expected = arithmetic::<u64>(a, b);
+            assert_eq!(arithmetic::<u128>(a, b), expected);
+            assert_eq!(arithmetic::<BigUint>(a, b), expected);
+        }
+    }
+
+    #[test]
+    fn overflow_is_caught() {
+        assert_eq!(CheckedNumber::checked_add(&u64::MAX, &1), None);
+        assert_eq!(
+            CheckedNumber::checked_mul(&(1_u128 << 64), &(1 << 64)),
+            None
+        );
+        assert_eq!(
+            <u64 as CheckedNumber>::from_str_radix("18446744073709551616", 10),
+            Err(ParseNumberError::TooBig)
+        );
+        assert!(matches!(
+            <u64 as CheckedNumber>::from_str_radix("12a", 10),
+            Err(ParseNumberError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn digits_in_any_base() {
+        for value in [0_u64, 1, 9, 10, 255, 256, 123_456_789] {
+            for radix in [2, 10, 16, 36] {
+                let digits = value.to_string_radix(radix);
+                assert_eq!(value.digit_count(radix), digits.len() as u32);
+                assert_eq!((value as u128).to_string_radix(radix), digits);
+                assert_eq!(BigUint::from_u64(value).to_string_radix(radix), digits);
+                assert_eq!(
+                    BigUint::from_u64(value).digit_count(radix),
+                    digits.len() as u32
+                );
+            }
+        }
+    }
+}
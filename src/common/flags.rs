@@ -0,0 +1,58 @@
+//! Reading the values given with flags on the command line, like `--input <path>`.
+
+// Each day only uses some of what is here.
+#![allow(dead_code)]
+
+use std::{fmt, str::FromStr};
+
+/// The value following a flag on the command line, returns None if the flag wasn't given.
+pub fn flag_value<'a>(args: &'a [String], flag: &str) -> Result<Option<&'a str>, String> {
+    match args.iter().position(|arg| arg == flag) {
+        Some(i) => args
+            .get(i + 1)
+            .map(|value| Some(value.as_str()))
+            .ok_or(format!("Missing value for {}", flag)),
+        None => Ok(None),
+    }
+}
+
+/// Like [`flag_value`], but parses the value as a number.
+pub fn flag_number<T>(args: &[String], flag: &str) -> Result<Option<T>, String>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    flag_value(args, flag)?
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|e| format!("Invalid value for {}: {}", flag, e))
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &str) -> Vec<String> {
+        args.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn values_follow_their_flag() {
+        let args = args("--input a.txt --seed 7 --big");
+        assert_eq!(flag_value(&args, "--input"), Ok(Some("a.txt")));
+        assert_eq!(flag_value(&args, "--output"), Ok(None));
+        assert_eq!(
+            flag_value(&args, "--big"),
+            Err("Missing value for --big".to_string())
+        );
+        assert_eq!(flag_number(&args, "--seed"), Ok(Some(7_u64)));
+        assert_eq!(flag_number::<i32>(&args, "--size"), Ok(None));
+        assert_eq!(
+            flag_number::<u32>(&args, "--input"),
+            Err("Invalid value for --input: invalid digit found in string".to_string())
+        );
+    }
+}
@@ -0,0 +1,63 @@
+//! A small pseudo random number generator (SplitMix64), so that anything random can be repeated
+//! from the same seed.
+
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number picked uniformly from `0..bound`, or None if the range is empty. Numbers from the
+    /// incomplete block at the top of the u64 range are rejected so that no result is favoured.
+    pub fn below(&mut self, bound: u64) -> Option<u64> {
+        if bound == 0 {
+            return None;
+        }
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return Some(value % bound);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_same_numbers() {
+        let mut a = Rng::new(7);
+        let mut b = Rng::new(7);
+        for _ in 0..10 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn below_stays_in_range() {
+        let mut rng = Rng::new(0);
+        assert_eq!(rng.below(0), None);
+        assert_eq!(rng.below(1), Some(0));
+        let mut seen = [false; 5];
+        for _ in 0..100 {
+            let value = rng.below(5).expect("range isn't empty");
+            seen[value as usize] = true;
+        }
+        assert_eq!(seen, [true; 5]);
+        assert!(rng.below(u64::MAX).is_some_and(|value| value < u64::MAX));
+    }
+}
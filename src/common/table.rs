@@ -0,0 +1,73 @@
+//! Printing rows of values as a plain text table with the columns lined up.
+
+// Each day only uses some of what is here.
+#![allow(dead_code)]
+
+#[derive(Clone, Copy)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// The lines of a table with a header row. Every column is as wide as its widest value, with
+/// two spaces between columns and nothing trailing at the end of a line.
+pub fn format_table<R: AsRef<[String]>>(
+    header: &[&str],
+    rows: &[R],
+    align: &[Align],
+) -> Vec<String> {
+    let widths: Vec<_> = (0..header.len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| row.as_ref()[column].len())
+                .chain([header[column].len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let format_row = |row: &[&str]| -> String {
+        let cells: Vec<_> = row
+            .iter()
+            .zip(&widths)
+            .zip(align)
+            .map(|((value, &width), align)| match align {
+                Align::Left => format!("{:<width$}", value),
+                Align::Right => format!("{:>width$}", value),
+            })
+            .collect();
+        cells.join("  ").trim_end().to_owned()
+    };
+    let rows = rows.iter().map(|row| {
+        let row: Vec<_> = row.as_ref().iter().map(String::as_str).collect();
+        format_row(&row)
+    });
+    [format_row(header)].into_iter().chain(rows).collect()
+}
+
+/// Print a table, see [`format_table`].
+pub fn print_table<R: AsRef<[String]>>(header: &[&str], rows: &[R], align: &[Align]) {
+    for line in format_table(header, rows, align) {
+        println!("{}", line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns_line_up() {
+        let rows = [
+            vec!["1".to_string(), "100".to_string(), "a b".to_string()],
+            vec!["22".to_string(), "5".to_string(), String::new()],
+        ];
+        assert_eq!(
+            format_table(
+                &["#", "sum", "IDs"],
+                &rows,
+                &[Align::Right, Align::Right, Align::Left]
+            ),
+            [" #  sum  IDs", " 1  100  a b", "22    5"]
+        );
+    }
+}